@@ -1,67 +1,257 @@
-use std::num::ParseIntError;
+use std::{
+    cmp::Reverse,
+    collections::BinaryHeap,
+    error::Error,
+    fmt::Display,
+    io::{self, BufRead},
+};
 
-use twentytwo::{print_solution, read_from_stdin};
+use twentytwo::{print_solution, util};
 
 type CalorieCount = u64;
 type ElvesWithCalorieSums = Vec<CalorieCount>;
 
 fn main() {
-    let input = read_from_stdin();
+    let options = Options::from_args();
+    let elves = read_elves(io::stdin().lock()).expect("Parse Elves");
 
     print_solution(
         1,
         1,
         "Find the Elf carrying the most Calories. How many total Calories is that Elf carrying?",
-        format!("{}", elf_with_most_calories(&input)),
+        format!("{}", elf_with_most_calories(&elves)),
     );
 
     print_solution(
         1,
         2,
         "Find the top three Elves carrying the most Calories. How many Calories are those Elves carrying in total?",
-        format!("{}", calorie_sum_of_3_elves_with_most_cals(&input)),
+        format!("{}", calorie_sum_of_3_elves_with_most_cals(&elves)),
     );
+
+    if let Some(n) = options.top {
+        println!("The top {} Elves:", n);
+        top_n(&elves, n)
+            .iter()
+            .for_each(|(elf, calories)| println!("Elf {}: {}", elf + 1, calories));
+        println!();
+    }
+
+    if options.stats {
+        println!("{}", CalorieStats::from_elves(&elves));
+        elves
+            .iter()
+            .enumerate()
+            .for_each(|(elf, calories)| println!("Elf {}: {}", elf + 1, calories));
+        println!();
+    }
+}
+
+struct Options {
+    /// Print the Elves carrying the most Calories
+    top: Option<usize>,
+    /// Print statistics and the total of every Elf
+    stats: bool,
+}
+
+impl Options {
+    fn from_args() -> Self {
+        let args = util::args();
+
+        Options {
+            top: util::parse_flag(&args, "--top"),
+            stats: util::has_flag(&args, "--stats"),
+        }
+    }
 }
 
 /// D1P1
-fn elf_with_most_calories(input: &str) -> u64 {
-    parse_input(input)
-        .expect("Parse Elves")
-        .into_iter()
-        .max()
-        .expect("Could not find max")
+fn elf_with_most_calories(elves: &[CalorieCount]) -> u64 {
+    top_n(elves, 1).iter().map(|(_, calories)| calories).sum()
 }
 
 /// D1P2
-fn calorie_sum_of_3_elves_with_most_cals(input: &str) -> u64 {
-    let mut elves_with_calorie_sums: Vec<u64> = parse_input(input).expect("Parse Elves");
-    elves_with_calorie_sums.sort();
-    elves_with_calorie_sums.reverse();
+fn calorie_sum_of_3_elves_with_most_cals(elves: &[CalorieCount]) -> u64 {
+    top_n(elves, 3).iter().map(|(_, calories)| calories).sum()
+}
+
+/// The index and total of the `n` Elves carrying the most Calories, most first. A min-heap holds the
+/// best `n` seen so far, so this is O(elves * log n). Ties go to the Elf listed first.
+fn top_n(elves: &[CalorieCount], n: usize) -> Vec<(usize, CalorieCount)> {
+    let mut heap: BinaryHeap<Reverse<(CalorieCount, Reverse<usize>)>> = BinaryHeap::with_capacity(n + 1);
+
+    for (elf, calories) in elves.iter().enumerate() {
+        heap.push(Reverse((*calories, Reverse(elf))));
 
-    elves_with_calorie_sums.iter().take(3).sum()
+        if heap.len() > n {
+            heap.pop();
+        }
+    }
+
+    heap.into_sorted_vec()
+        .into_iter()
+        .map(|Reverse((calories, Reverse(elf)))| (elf, calories))
+        .collect()
+}
+
+#[derive(Debug, PartialEq)]
+struct CalorieStats {
+    elves: usize,
+    total: CalorieCount,
+    mean: f64,
+    median: f64,
+}
+
+impl CalorieStats {
+    fn from_elves(elves: &[CalorieCount]) -> Self {
+        let total: CalorieCount = elves.iter().sum();
+        let mut sorted = elves.to_vec();
+        sorted.sort_unstable();
+
+        let median = match sorted.len() {
+            0 => 0.0,
+            len if len % 2 == 1 => sorted[len / 2] as f64,
+            len => (sorted[len / 2 - 1] + sorted[len / 2]) as f64 / 2.0,
+        };
+
+        CalorieStats {
+            elves: elves.len(),
+            total,
+            mean: if elves.is_empty() {
+                0.0
+            } else {
+                total as f64 / elves.len() as f64
+            },
+            median,
+        }
+    }
 }
 
-fn parse_input(input: &str) -> Result<ElvesWithCalorieSums, ParseIntError> {
-    input.trim().split("\n\n").map(parse_elf).collect()
+impl Display for CalorieStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "Elves: {}", self.elves)?;
+        writeln!(f, "Total Calories: {}", self.total)?;
+        writeln!(f, "Mean: {:.1}", self.mean)?;
+        write!(f, "Median: {:.1}", self.median)
+    }
 }
 
-fn parse_elf(input: &str) -> Result<CalorieCount, ParseIntError> {
-    input.split('\n').map(|cal| cal.parse::<u64>()).sum()
+#[derive(Debug)]
+enum ParseElvesError {
+    Read(io::Error),
+    InvalidCalories { line: usize, text: String },
+}
+
+impl Error for ParseElvesError {}
+
+impl Display for ParseElvesError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParseElvesError::Read(error) => write!(f, "Failed to read the inventory: {}", error),
+            ParseElvesError::InvalidCalories { line, text } => {
+                write!(f, "Invalid Calories '{}' on line {}", text, line)
+            }
+        }
+    }
+}
+
+/// Read the inventory one line at a time, so it never has to be in memory all at once.
+/// Lines are trimmed, so CRLF line endings, trailing whitespace and blank lines of spaces are fine.
+fn read_elves(reader: impl BufRead) -> Result<ElvesWithCalorieSums, ParseElvesError> {
+    let mut elves = Vec::new();
+    let mut current: Option<CalorieCount> = None;
+
+    for (index, line) in reader.lines().enumerate() {
+        let line = line.map_err(ParseElvesError::Read)?;
+        let line = line.trim();
+
+        if line.is_empty() {
+            elves.extend(current.take());
+            continue;
+        }
+
+        let calories = line
+            .parse::<CalorieCount>()
+            .map_err(|_| ParseElvesError::InvalidCalories {
+                line: index + 1,
+                text: line.to_string(),
+            })?;
+
+        current = Some(current.unwrap_or(0) + calories);
+    }
+
+    elves.extend(current);
+    Ok(elves)
 }
 
 #[cfg(test)]
 mod tests {
+    use std::io::{BufReader, Read};
+
     use super::*;
 
-    const PUZZLE_INPUT: &str = include_str!("../../puzzle_inputs/day1.txt");
+    const EXAMPLE: &str = "1000\n2000\n3000\n\n4000\n\n5000\n6000\n\n7000\n8000\n9000\n\n10000\n";
+
+    fn elves(input: &str) -> ElvesWithCalorieSums {
+        read_elves(input.as_bytes()).unwrap()
+    }
 
     #[test]
-    fn solves_d1p1() {
-        assert_eq!(elf_with_most_calories(PUZZLE_INPUT), 69528);
+    fn solves_d1p1_example() {
+        assert_eq!(elf_with_most_calories(&elves(EXAMPLE)), 24000);
     }
 
     #[test]
-    fn solves_d1p2() {
-        assert_eq!(calorie_sum_of_3_elves_with_most_cals(PUZZLE_INPUT), 206152);
+    fn solves_d1p2_example() {
+        assert_eq!(calorie_sum_of_3_elves_with_most_cals(&elves(EXAMPLE)), 45000);
+    }
+
+    #[test]
+    fn finds_the_top_n_elves() {
+        let elves = elves(EXAMPLE);
+
+        assert_eq!(top_n(&elves, 0), vec![]);
+        assert_eq!(top_n(&elves, 2), vec![(3, 24000), (2, 11000)]);
+        assert_eq!(top_n(&elves, 10).len(), 5);
+        assert_eq!(top_n(&[5, 7, 5], 2), vec![(1, 7), (0, 5)]);
+    }
+
+    #[test]
+    fn calculates_statistics() {
+        assert_eq!(
+            CalorieStats::from_elves(&elves(EXAMPLE)),
+            CalorieStats {
+                elves: 5,
+                total: 55000,
+                mean: 11000.0,
+                median: 10000.0
+            }
+        );
+        assert_eq!(CalorieStats::from_elves(&[1, 2, 3, 10]).median, 2.5);
+    }
+
+    #[test]
+    fn parses_crlf_and_whitespace() {
+        let messy = "\r\n1000\r\n2000  \r\n   \r\n\r\n 4000\r\n\t\r\n";
+
+        assert_eq!(elves(messy), vec![3000, 4000]);
+        assert!(matches!(
+            read_elves("1000\nabc\n".as_bytes()),
+            Err(ParseElvesError::InvalidCalories { line: 2, .. })
+        ));
+    }
+
+    #[test]
+    fn streams_a_large_inventory() {
+        // A hundred thousand elves carrying 1, 2, 3, ... Calories in ten snacks each
+        let inventory = (1..=100_000u64)
+            .flat_map(|elf| std::iter::repeat_n(format!("{}\n", elf), 10).chain(std::iter::once(String::from("\n"))))
+            .flat_map(String::into_bytes)
+            .collect::<Vec<u8>>();
+        let elves = read_elves(BufReader::new(inventory.as_slice().take(u64::MAX))).unwrap();
+
+        assert_eq!(elves.len(), 100_000);
+        assert_eq!(top_n(&elves, 2), vec![(99_999, 1_000_000), (99_998, 999_990)]);
+        assert_eq!(CalorieStats::from_elves(&elves).median, 500_005.0);
     }
 }
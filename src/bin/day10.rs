@@ -1,109 +1,672 @@
-use itertools::{unfold, Itertools};
-use twentytwo::{print_solution, read_from_stdin};
+use itertools::Itertools;
+use std::{
+    collections::{HashMap, HashSet},
+    error::Error,
+    fmt::Display,
+    str::FromStr,
+};
+use twentytwo::{print_solution, read_from_stdin, util};
 
 fn main() {
     let input = read_from_stdin();
+    let options = Options::from_args();
 
     print_solution(
         10,
         1,
         "Find the signal strength during the 20th, 60th, 100th, 140th, 180th, and 220th cycles. What is the sum of these six signal strengths?",
-        format!("{}", signal_sum(&input)),
+        util::describe(signal_sum(&input, &options.sample_cycles)),
     );
 
+    let screen = render_crt(&input, &options.crt);
+
     print_solution(
         10,
         2,
         "Render the image given by your program. What eight capital letters appear on your CRT?",
-        render_crt(&input),
+        util::describe(screen.as_ref().map(|screen| {
+            let image = if options.unicode {
+                screen.to_unicode()
+            } else {
+                screen.to_ascii()
+            };
+
+            format!("{}{}", image, util::describe(decode_crt(&screen.to_ascii())))
+        })),
     );
+
+    if let (Ok(screen), Some(path)) = (&screen, &options.output) {
+        let contents = if path.ends_with(".png") {
+            screen.to_png(8)
+        } else {
+            screen.to_pbm().into_bytes()
+        };
+
+        std::fs::write(path, contents).expect("write screen to file");
+    }
+
+    if options.trace || !options.breakpoints.is_empty() {
+        if let Err(error) = debug_program(&input, &options) {
+            println!("{}", error);
+        }
+    }
+}
+
+struct Options {
+    /// The screen geometry, from `--width`, `--height` and `--sprite-width`
+    crt: CrtConfig,
+    /// The cycles sampled for the signal strength, like `10,30`
+    sample_cycles: Vec<usize>,
+    /// Print the screen with Unicode block characters
+    unicode: bool,
+    /// Write the screen to a `.png` or `.pbm` file
+    output: Option<String>,
+    /// Print every cycle of the debugger
+    trace: bool,
+    /// Stop the debugger at these instructions, `--breakpoint` can be given more than once
+    breakpoints: Vec<usize>,
+    /// Cycle costs like `addx=3`, `--cycle-cost` can be given more than once
+    cycle_costs: Vec<(Opcode, usize)>,
+    /// Stop the debugger after this many cycles
+    max_cycles: usize,
+}
+
+impl Options {
+    fn from_args() -> Self {
+        let args = util::args();
+
+        let default_crt = CrtConfig::default();
+        let crt = CrtConfig {
+            width: util::parse_flag(&args, "--width").unwrap_or(default_crt.width),
+            height: util::parse_flag(&args, "--height").unwrap_or(default_crt.height),
+            sprite_width: util::parse_flag(&args, "--sprite-width").unwrap_or(default_crt.sprite_width),
+        };
+
+        let sample_cycles = match util::flag_value(&args, "--sample-cycles") {
+            Some(cycles) => cycles
+                .split(',')
+                .map(|cycle| cycle.trim().parse::<usize>().expect("parse sample cycle"))
+                .collect(),
+            None => SAMPLE_CYCLES.to_vec(),
+        };
+
+        let breakpoints = util::flag_values(&args, "--breakpoint")
+            .map(|pc| pc.parse::<usize>().expect("parse breakpoint"))
+            .collect();
+
+        let cycle_costs = util::flag_values(&args, "--cycle-cost")
+            .map(|cost| {
+                let (mnemonic, cycles) = cost.split_once('=').expect("cycle cost as <instruction>=<cycles>");
+                let opcode = Opcode::from_mnemonic(mnemonic).expect("known instruction for cycle cost");
+
+                (opcode, cycles.parse::<usize>().expect("parse cycle cost"))
+            })
+            .collect();
+
+        Options {
+            crt,
+            sample_cycles,
+            unicode: util::has_flag(&args, "--unicode"),
+            output: util::flag_value(&args, "--output").map(String::from),
+            max_cycles: util::parse_flag(&args, "--max-cycles").unwrap_or(100_000),
+            trace: util::has_flag(&args, "--trace"),
+            breakpoints,
+            cycle_costs,
+        }
+    }
+}
+
+fn debug_program(input: &str, options: &Options) -> Result<(), ParseInstructionError> {
+    let mut cpu = Cpu::new(parse_input(input)?);
+    cpu.cycle_limit = Some(options.max_cycles);
+
+    for (opcode, cycles) in options.cycle_costs.iter() {
+        cpu.set_cycle_cost(*opcode, *cycles);
+    }
+
+    for pc in options.breakpoints.iter() {
+        cpu.add_breakpoint(*pc);
+    }
+
+    loop {
+        let stop_reason = cpu.run(|entry| {
+            if options.trace {
+                println!("{}", entry)
+            }
+        });
+
+        match stop_reason {
+            StopReason::Breakpoint(pc) => {
+                println!("Breakpoint at pc {} after cycle {}: {}", pc, cpu.cycle, cpu.registers)
+            }
+            StopReason::CycleLimit => {
+                break println!("Stopped at the cycle limit of {}: {}", cpu.cycle, cpu.registers)
+            }
+            StopReason::Halted => break println!("Halted after cycle {}: {}", cpu.cycle, cpu.registers),
+        }
+    }
+
+    Ok(())
 }
 
+/// The cycles sampled for the signal strength in the puzzle
+const SAMPLE_CYCLES: [usize; 6] = [20, 60, 100, 140, 180, 220];
+
 // D10P1
-fn signal_sum(input: &str) -> i64 {
-    let instructions = parse_input(input);
-    let mut signal_strengths = signal_strengths(instructions);
+fn signal_sum(input: &str, sample_cycles: &[usize]) -> Result<i64, ParseInstructionError> {
+    let instructions = parse_input(input)?;
+    let signal_strengths: Vec<i64> = signal_strengths(instructions).collect();
 
-    [(20, 20), (40, 60), (40, 100), (40, 140), (40, 180), (40, 220)]
+    Ok(sample_cycles
         .iter()
-        .map(|(steps, step_sum)| step_sum * signal_strengths.nth(steps - 1).expect("iter pos"))
-        .sum()
+        .map(|cycle| {
+            let x = cycle
+                .checked_sub(1)
+                .and_then(|index| signal_strengths.get(index))
+                .unwrap_or_else(|| panic!("the program does not run during cycle {}", cycle));
+
+            *cycle as i64 * x
+        })
+        .sum())
 }
 
 // D10P2
-fn render_crt(input: &str) -> String {
-    let instructions = parse_input(input);
-    let signal_strengths = signal_strengths(instructions);
+fn render_crt(input: &str, config: &CrtConfig) -> Result<Screen, ParseInstructionError> {
+    let instructions = parse_input(input)?;
 
-    signal_strengths
-        .chunks(40)
-        .into_iter()
-        .map(|chunk| {
-            chunk
-                .enumerate()
-                .map(|(i, strength)| {
-                    let diff = (strength - (i as i64)).abs();
-
-                    if diff == 0 || diff == 1 {
-                        "#"
-                    } else {
-                        "."
-                    }
-                })
-                .collect::<String>()
+    Ok(Screen::draw(signal_strengths(instructions), config))
+}
+
+// CRT ----->
+
+#[derive(Clone, Copy, Debug)]
+struct CrtConfig {
+    width: usize,
+    height: usize,
+    /// The number of pixels the sprite covers, centered on X
+    sprite_width: usize,
+}
+
+impl Default for CrtConfig {
+    fn default() -> Self {
+        CrtConfig {
+            width: 40,
+            height: 6,
+            sprite_width: 3,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Screen {
+    width: usize,
+    height: usize,
+    /// Row by row, true if the pixel is lit
+    pixels: Vec<bool>,
+}
+
+impl Screen {
+    /// Draw one pixel per cycle, lit when the sprite at X covers the column being drawn.
+    /// Pixels after the end of the program stay dark.
+    fn draw(signal_strengths: impl Iterator<Item = i64>, config: &CrtConfig) -> Self {
+        let mut pixels = vec![false; config.width * config.height];
+
+        for (index, x) in signal_strengths.take(pixels.len()).enumerate() {
+            let column = (index % config.width) as i64;
+            let sprite_start = x - (config.sprite_width as i64 - 1) / 2;
+
+            pixels[index] = sprite_start <= column && column < sprite_start + config.sprite_width as i64;
+        }
+
+        Screen {
+            width: config.width,
+            height: config.height,
+            pixels,
+        }
+    }
+
+    fn rows(&self) -> impl Iterator<Item = &[bool]> {
+        // chunks panics on a chunk size of 0
+        self.pixels.chunks(self.width.max(1))
+    }
+
+    /// `#` for lit pixels and `.` for dark pixels, like the puzzle text
+    fn to_ascii(&self) -> String {
+        self.rows()
+            .map(|row| row.iter().map(|lit| if *lit { '#' } else { '.' }).collect::<String>() + "\n")
+            .collect()
+    }
+
+    /// Two rows of pixels per line, using the Unicode half and full block characters
+    fn to_unicode(&self) -> String {
+        self.rows()
+            .chunks(2)
+            .into_iter()
+            .map(|mut rows| {
+                let upper = rows.next().expect("chunks are never empty");
+                let lower = rows.next();
+
+                (0..self.width)
+                    .map(
+                        |column| match (upper[column], lower.is_some_and(|lower| lower[column])) {
+                            (true, true) => '█',
+                            (true, false) => '▀',
+                            (false, true) => '▄',
+                            (false, false) => ' ',
+                        },
+                    )
+                    .collect::<String>()
+                    + "\n"
+            })
+            .collect()
+    }
+
+    /// A plain PBM image, where 1 is a lit pixel
+    fn to_pbm(&self) -> String {
+        let mut pbm = format!("P1\n{} {}\n", self.width, self.height);
+
+        for row in self.rows() {
+            pbm += &row.iter().map(|lit| if *lit { "1" } else { "0" }).join(" ");
+            pbm += "\n";
+        }
+
+        pbm
+    }
+
+    /// A grayscale PNG image with white lit pixels on black, each pixel scaled up to a square of `scale` pixels
+    fn to_png(&self, scale: usize) -> Vec<u8> {
+        let scale = scale.max(1);
+        let mut png = Vec::new();
+
+        let mut encoder = png::Encoder::new(&mut png, (self.width * scale) as u32, (self.height * scale) as u32);
+        encoder.set_color(png::ColorType::Grayscale);
+        encoder.set_depth(png::BitDepth::Eight);
+
+        let data: Vec<u8> = self
+            .rows()
+            .flat_map(|row| {
+                let line: Vec<u8> = row
+                    .iter()
+                    .flat_map(|lit| std::iter::repeat_n(if *lit { 255 } else { 0 }, scale))
+                    .collect();
+
+                std::iter::repeat_n(line, scale).flatten()
+            })
+            .collect();
+
+        let mut writer = encoder.write_header().expect("write png header");
+        writer.write_image_data(&data).expect("write png data");
+        writer.finish().expect("finish png");
+
+        png
+    }
+}
+
+// OCR ----->
+
+const GLYPH_WIDTH: usize = 4;
+const GLYPH_HEIGHT: usize = 6;
+
+/// The letters of the Advent of Code font, which are 4 pixels wide and 6 pixels tall
+const GLYPHS: [(char, [&str; GLYPH_HEIGHT]); 17] = [
+    ('A', [".##.", "#..#", "#..#", "####", "#..#", "#..#"]),
+    ('B', ["###.", "#..#", "###.", "#..#", "#..#", "###."]),
+    ('C', [".##.", "#..#", "#...", "#...", "#..#", ".##."]),
+    ('E', ["####", "#...", "###.", "#...", "#...", "####"]),
+    ('F', ["####", "#...", "###.", "#...", "#...", "#..."]),
+    ('G', [".##.", "#..#", "#...", "#.##", "#..#", ".###"]),
+    ('H', ["#..#", "#..#", "####", "#..#", "#..#", "#..#"]),
+    ('I', [".###", "..#.", "..#.", "..#.", "..#.", ".###"]),
+    ('J', ["..##", "...#", "...#", "...#", "#..#", ".##."]),
+    ('K', ["#..#", "#.#.", "##..", "#.#.", "#.#.", "#..#"]),
+    ('L', ["#...", "#...", "#...", "#...", "#...", "####"]),
+    ('O', [".##.", "#..#", "#..#", "#..#", "#..#", ".##."]),
+    ('P', ["###.", "#..#", "#..#", "###.", "#...", "#..."]),
+    ('R', ["###.", "#..#", "#..#", "###.", "#.#.", "#..#"]),
+    ('S', [".###", "#...", "#...", ".##.", "...#", "###."]),
+    ('U', ["#..#", "#..#", "#..#", "#..#", "#..#", ".##."]),
+    ('Z', ["####", "...#", "..#.", ".#..", "#...", "####"]),
+];
+
+#[derive(Debug, PartialEq, Eq)]
+enum OcrError {
+    InvalidHeight(usize),
+    UnknownGlyph { column: usize },
+}
+
+impl Error for OcrError {}
+
+impl Display for OcrError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            OcrError::InvalidHeight(height) => {
+                write!(f, "Expected an image {} pixels tall, got {}", GLYPH_HEIGHT, height)
+            }
+            OcrError::UnknownGlyph { column } => write!(f, "Unknown glyph at column {}", column),
+        }
+    }
+}
+
+/// Read the letters of a rendered image. Every letter takes up 4 columns, followed by an empty column.
+fn decode_crt(image: &str) -> Result<String, OcrError> {
+    let rows: Vec<&str> = image.lines().collect();
+
+    if rows.len() != GLYPH_HEIGHT {
+        return Err(OcrError::InvalidHeight(rows.len()));
+    }
+
+    let width = rows.iter().map(|row| row.len()).max().unwrap_or(0);
+
+    (0..width)
+        .step_by(GLYPH_WIDTH + 1)
+        .map(|column| {
+            let glyph: Vec<&str> = rows
+                .iter()
+                .map(|row| row.get(column..column + GLYPH_WIDTH).unwrap_or(""))
+                .collect();
+
+            GLYPHS
+                .iter()
+                .find(|(_, pixels)| pixels.as_slice() == glyph.as_slice())
+                .map(|(letter, _)| *letter)
+                .ok_or(OcrError::UnknownGlyph { column })
         })
-        .map(|line| format!("{}\n", line))
         .collect()
 }
 
-// My first use of unfold 🎉
-// Creates an iterator of signal strengths from a list of instructions
+// Creates an iterator of the X register during each cycle of a program
 fn signal_strengths(instructions: Vec<Instruction>) -> impl Iterator<Item = i64> {
-    unfold((0_usize, 1), move |(i, strength)| match &instructions.get(*i) {
-        Some(Instruction::AddX(value)) => {
-            let return_value = vec![*strength, *strength];
-            *strength += value;
-            *i += 1;
+    Cpu::new(instructions).map(|entry| entry.x)
+}
+
+// CPU ----->
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+struct Registers {
+    x: i64,
+    y: i64,
+}
+
+impl Display for Registers {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "X={} Y={}", self.x, self.y)
+    }
+}
+
+/// What the CPU was doing during a single cycle. The registers are the values during the cycle,
+/// so an instruction only changes them after its last cycle.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct TraceEntry {
+    cycle: usize,
+    pc: usize,
+    x: i64,
+    instruction: Instruction,
+}
+
+impl Display for TraceEntry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "cycle {:>5}  pc {:>4}  X {:>5}  {}",
+            self.cycle, self.pc, self.x, self.instruction
+        )
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum StopReason {
+    Breakpoint(usize),
+    CycleLimit,
+    Halted,
+}
+
+struct Cpu {
+    program: Vec<Instruction>,
+    registers: Registers,
+    pc: usize,
+    /// The number of cycles completed so far
+    cycle: usize,
+    /// The number of cycles spent on the current instruction
+    cycles_in_instruction: usize,
+    cycle_costs: HashMap<Opcode, usize>,
+    breakpoints: HashSet<usize>,
+    /// The breakpoint `run` last stopped at, so running again continues past it
+    stopped_at: Option<usize>,
+    /// Stop `run` after this many cycles, since a `jmp` can loop forever
+    cycle_limit: Option<usize>,
+}
+
+impl Cpu {
+    fn new(program: Vec<Instruction>) -> Self {
+        let cycle_costs = [
+            (Opcode::AddX, 2),
+            (Opcode::AddY, 2),
+            (Opcode::Mul, 2),
+            (Opcode::Jmp, 1),
+            (Opcode::NoOp, 1),
+        ]
+        .into_iter()
+        .collect();
 
-            Some(return_value)
+        Cpu {
+            program,
+            registers: Registers { x: 1, y: 0 },
+            pc: 0,
+            cycle: 0,
+            cycles_in_instruction: 0,
+            cycle_costs,
+            breakpoints: HashSet::new(),
+            stopped_at: None,
+            cycle_limit: None,
         }
-        Some(Instruction::NoOp) => {
-            *i += 1;
-            Some(vec![*strength])
+    }
+
+    /// Change how many cycles an instruction takes to complete (at least 1)
+    fn set_cycle_cost(&mut self, opcode: Opcode, cycles: usize) {
+        self.cycle_costs.insert(opcode, cycles.max(1));
+    }
+
+    /// Stop `run` when the program counter reaches this instruction
+    fn add_breakpoint(&mut self, pc: usize) {
+        self.breakpoints.insert(pc);
+    }
+
+    /// Run until the program counter reaches a breakpoint, the cycle limit is hit or the program halts.
+    /// Every cycle is passed to `on_cycle`. Running again continues from the breakpoint.
+    fn run(&mut self, mut on_cycle: impl FnMut(&TraceEntry)) -> StopReason {
+        let resuming_from = self.stopped_at.take();
+
+        // A breakpoint on the instruction the CPU is about to start stops before the first cycle
+        if self.cycles_in_instruction == 0 && self.breakpoints.contains(&self.pc) && resuming_from != Some(self.pc) {
+            self.stopped_at = Some(self.pc);
+            return StopReason::Breakpoint(self.pc);
         }
-        None => None,
-    })
-    .flatten()
+
+        loop {
+            if self.cycle_limit.is_some_and(|limit| self.cycle >= limit) {
+                break StopReason::CycleLimit;
+            }
+
+            match self.step() {
+                None => break StopReason::Halted,
+                Some(entry) => {
+                    on_cycle(&entry);
+
+                    if self.cycles_in_instruction == 0 && self.breakpoints.contains(&self.pc) {
+                        self.stopped_at = Some(self.pc);
+                        break StopReason::Breakpoint(self.pc);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Run a single cycle. Returns None when the program counter is outside of the program.
+    fn step(&mut self) -> Option<TraceEntry> {
+        let instruction = *self.program.get(self.pc)?;
+
+        self.cycle += 1;
+        self.cycles_in_instruction += 1;
+
+        let entry = TraceEntry {
+            cycle: self.cycle,
+            pc: self.pc,
+            x: self.registers.x,
+            instruction,
+        };
+
+        if self.cycles_in_instruction >= self.cycle_costs[&instruction.opcode()] {
+            self.execute(instruction);
+            self.cycles_in_instruction = 0;
+        }
+
+        Some(entry)
+    }
+
+    fn execute(&mut self, instruction: Instruction) {
+        match instruction {
+            Instruction::AddX(value) => self.registers.x += value,
+            Instruction::AddY(value) => self.registers.y += value,
+            Instruction::Mul(value) => self.registers.x *= value,
+            Instruction::NoOp | Instruction::Jmp(_) => {}
+        }
+
+        self.pc = match instruction {
+            // Jumping outside of the program halts it
+            Instruction::Jmp(offset) => self
+                .pc
+                .checked_add_signed(offset as isize)
+                .unwrap_or(self.program.len()),
+            _ => self.pc + 1,
+        };
+    }
+}
+
+// The CPU is an iterator of every cycle it runs, ending when the program halts
+impl Iterator for Cpu {
+    type Item = TraceEntry;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.step()
+    }
 }
 
 // Parsing ----->
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+enum Opcode {
+    AddX,
+    AddY,
+    Mul,
+    Jmp,
+    NoOp,
+}
+
+/// To add an instruction, add it here and to Opcode, then teach the parser, the cycle costs and `Cpu::execute` about it
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 enum Instruction {
     AddX(i64),
+    AddY(i64),
+    Mul(i64),
+    Jmp(i64),
     NoOp,
 }
 
-impl From<&str> for Instruction {
-    fn from(value: &str) -> Self {
-        if let Some(amount) = value.strip_prefix("addx ") {
-            let amount_i32 = amount.parse::<i64>().expect("parse amount");
-            Instruction::AddX(amount_i32)
-        } else if value == "noop" {
-            Instruction::NoOp
-        } else {
-            println!("failed to parse: '{}'", value);
-            panic!("parse instructions");
+impl Opcode {
+    fn from_mnemonic(mnemonic: &str) -> Option<Opcode> {
+        match mnemonic {
+            "addx" => Some(Opcode::AddX),
+            "addy" => Some(Opcode::AddY),
+            "mul" => Some(Opcode::Mul),
+            "jmp" => Some(Opcode::Jmp),
+            "noop" => Some(Opcode::NoOp),
+            _ => None,
+        }
+    }
+
+    fn mnemonic(&self) -> &'static str {
+        match self {
+            Opcode::AddX => "addx",
+            Opcode::AddY => "addy",
+            Opcode::Mul => "mul",
+            Opcode::Jmp => "jmp",
+            Opcode::NoOp => "noop",
         }
     }
 }
 
-fn parse_input(input: &str) -> Vec<Instruction> {
-    input.lines().map(Instruction::from).collect()
+impl Instruction {
+    fn opcode(&self) -> Opcode {
+        match self {
+            Instruction::AddX(_) => Opcode::AddX,
+            Instruction::AddY(_) => Opcode::AddY,
+            Instruction::Mul(_) => Opcode::Mul,
+            Instruction::Jmp(_) => Opcode::Jmp,
+            Instruction::NoOp => Opcode::NoOp,
+        }
+    }
+}
+
+impl Display for Instruction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Instruction::AddX(value)
+            | Instruction::AddY(value)
+            | Instruction::Mul(value)
+            | Instruction::Jmp(value) => {
+                write!(f, "{} {}", self.opcode().mnemonic(), value)
+            }
+            Instruction::NoOp => write!(f, "{}", self.opcode().mnemonic()),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+struct ParseInstructionError(String);
+
+impl Error for ParseInstructionError {}
+
+impl Display for ParseInstructionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Failed to parse instruction: '{}'", self.0)
+    }
+}
+
+impl FromStr for Instruction {
+    type Err = ParseInstructionError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let error = || ParseInstructionError(String::from(value));
+
+        let (mnemonic, amount) = match value.split_once(' ') {
+            Some((mnemonic, amount)) => (mnemonic, Some(amount.parse::<i64>().map_err(|_| error())?)),
+            None => (value, None),
+        };
+
+        let instruction = match (Opcode::from_mnemonic(mnemonic).ok_or_else(error)?, amount) {
+            (Opcode::AddX, Some(amount)) => Instruction::AddX(amount),
+            (Opcode::AddY, Some(amount)) => Instruction::AddY(amount),
+            (Opcode::Mul, Some(amount)) => Instruction::Mul(amount),
+            (Opcode::Jmp, Some(amount)) => Instruction::Jmp(amount),
+            (Opcode::NoOp, None) => Instruction::NoOp,
+            _ => return Err(error()),
+        };
+
+        Ok(instruction)
+    }
+}
+
+fn parse_input(input: &str) -> Result<Vec<Instruction>, ParseInstructionError> {
+    input.lines().map(Instruction::from_str).collect()
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::{render_crt, signal_sum};
+    use crate::{
+        decode_crt, parse_input, render_crt, signal_sum, Cpu, CrtConfig, Instruction, OcrError, Opcode,
+        ParseInstructionError, StopReason, TraceEntry, GLYPHS, GLYPH_HEIGHT, SAMPLE_CYCLES,
+    };
+    use itertools::Itertools;
 
     const EXAMPLE: &str = r#"addx 15
 addx -11
@@ -260,8 +823,6 @@ noop"#;
 #######.......#######.......#######.....
 "#;
 
-    const PUZZLE_INPUT: &str = include_str!("../../puzzle_inputs/day10.txt");
-
     const PUZZLE_ANSWER: &str = r#"###..#..#..##...##...##..###..#..#.####.
 #..#.#..#.#..#.#..#.#..#.#..#.#..#....#.
 ###..#..#.#....#..#.#....###..#..#...#..
@@ -272,21 +833,191 @@ noop"#;
 
     #[test]
     fn solves_p1_example() {
-        assert_eq!(signal_sum(EXAMPLE), 13140);
+        assert_eq!(signal_sum(EXAMPLE, &SAMPLE_CYCLES), Ok(13140));
     }
 
     #[test]
-    fn solves_p1() {
-        assert_eq!(signal_sum(PUZZLE_INPUT), 14920);
+    fn solves_p2_example() {
+        assert_eq!(
+            render_crt(EXAMPLE, &CrtConfig::default()).unwrap().to_ascii(),
+            EXAMPLE_IMAGE
+        );
     }
 
     #[test]
-    fn solves_p2_example() {
-        assert_eq!(render_crt(EXAMPLE), EXAMPLE_IMAGE);
+    fn samples_other_cycles() {
+        assert_eq!(signal_sum(EXAMPLE, &[20, 20]), Ok(840));
+        assert_eq!(signal_sum(EXAMPLE, &[1, 2, 3]), Ok(1 + 2 + 3 * 16));
+    }
+
+    #[test]
+    fn renders_other_geometries() {
+        let config = CrtConfig {
+            width: 8,
+            height: 3,
+            sprite_width: 5,
+        };
+
+        assert_eq!(
+            render_crt(EXAMPLE, &config).unwrap().to_ascii(),
+            "##..##..\n......#.\n........\n"
+        );
+
+        let config = CrtConfig {
+            width: 8,
+            height: 2,
+            sprite_width: 1,
+        };
+
+        assert_eq!(render_crt(EXAMPLE, &config).unwrap().to_ascii(), ".#...#..\n........\n");
+    }
+
+    #[test]
+    fn renders_unicode_and_pbm() {
+        let config = CrtConfig {
+            width: 4,
+            height: 3,
+            sprite_width: 3,
+        };
+        let screen = render_crt(EXAMPLE, &config).unwrap();
+
+        assert_eq!(screen.to_ascii(), "##..\n....\n....\n");
+        assert_eq!(screen.to_unicode(), "▀▀  \n    \n");
+        assert_eq!(screen.to_pbm(), "P1\n4 3\n1 1 0 0\n0 0 0 0\n0 0 0 0\n");
+    }
+
+    #[test]
+    fn renders_png() {
+        let png = render_crt(EXAMPLE, &CrtConfig::default()).unwrap().to_png(2);
+
+        assert_eq!(&png[1..4], b"PNG");
+        // The IHDR chunk holds the width and height
+        assert_eq!(&png[16..24], &[0, 0, 0, 80, 0, 0, 0, 12]);
+    }
+
+    #[test]
+    fn decodes_p2() {
+        assert_eq!(decode_crt(PUZZLE_ANSWER), Ok(String::from("BUCACBUZ")));
+    }
+
+    #[test]
+    fn decodes_every_glyph() {
+        let image: String = (0..GLYPH_HEIGHT)
+            .map(|row| GLYPHS.iter().map(|(_, pixels)| pixels[row]).join(".") + "\n")
+            .collect();
+
+        assert_eq!(decode_crt(&image), Ok(String::from("ABCEFGHIJKLOPRSUZ")));
+        assert_eq!(
+            decode_crt("#..#.####\n#..#.#...\n####.###.\n#..#.#...\n#..#.#...\n#..#.####\n"),
+            Ok(String::from("HE"))
+        );
+    }
+
+    #[test]
+    fn names_the_column_of_unknown_glyphs() {
+        assert_eq!(decode_crt(EXAMPLE_IMAGE), Err(OcrError::UnknownGlyph { column: 0 }));
+
+        let mut smudged = String::from(PUZZLE_ANSWER);
+        smudged.replace_range(15..16, "#");
+        assert_eq!(
+            decode_crt(&smudged).unwrap_err().to_string(),
+            "Unknown glyph at column 15"
+        );
+
+        assert_eq!(decode_crt("####\n"), Err(OcrError::InvalidHeight(1)));
+    }
+
+    #[test]
+    fn traces_every_cycle() {
+        let mut cpu = Cpu::new(parse_input("noop\naddx 3\naddx -5").unwrap());
+        let trace: Vec<TraceEntry> = cpu.by_ref().collect();
+
+        let entry = |cycle, pc, x, instruction| TraceEntry {
+            cycle,
+            pc,
+            x,
+            instruction,
+        };
+
+        assert_eq!(
+            trace,
+            vec![
+                entry(1, 0, 1, Instruction::NoOp),
+                entry(2, 1, 1, Instruction::AddX(3)),
+                entry(3, 1, 1, Instruction::AddX(3)),
+                entry(4, 2, 4, Instruction::AddX(-5)),
+                entry(5, 2, 4, Instruction::AddX(-5)),
+            ]
+        );
+        assert_eq!(cpu.registers.x, -1);
     }
 
     #[test]
-    fn solves_p2() {
-        assert_eq!(render_crt(PUZZLE_INPUT), PUZZLE_ANSWER);
+    fn stops_at_breakpoints() {
+        let mut cpu = Cpu::new(parse_input("addx 2\nnoop\naddx 3\nnoop").unwrap());
+        cpu.add_breakpoint(2);
+
+        assert_eq!(cpu.run(|_| {}), StopReason::Breakpoint(2));
+        assert_eq!((cpu.cycle, cpu.registers.x), (3, 3));
+
+        assert_eq!(cpu.run(|_| {}), StopReason::Halted);
+        assert_eq!((cpu.cycle, cpu.registers.x), (6, 6));
+    }
+
+    #[test]
+    fn stops_at_a_breakpoint_on_the_first_instruction() {
+        let mut cpu = Cpu::new(
+            parse_input(
+                "noop
+addx 2
+jmp -2",
+            )
+            .unwrap(),
+        );
+        cpu.add_breakpoint(0);
+
+        assert_eq!(cpu.run(|_| {}), StopReason::Breakpoint(0));
+        assert_eq!((cpu.cycle, cpu.registers.x), (0, 1));
+
+        // Running again continues past the breakpoint, until the jump gets back to it
+        assert_eq!(cpu.run(|_| {}), StopReason::Breakpoint(0));
+        assert_eq!((cpu.cycle, cpu.registers.x), (4, 3));
+    }
+
+    #[test]
+    fn runs_extended_instructions_with_custom_cycle_costs() {
+        let program = parse_input("addy 5\nmul 3\njmp 2\naddx 100\naddx 1").unwrap();
+        let mut cpu = Cpu::new(program);
+        cpu.set_cycle_cost(Opcode::Mul, 4);
+
+        assert_eq!(cpu.run(|_| {}), StopReason::Halted);
+        assert_eq!(cpu.registers.x, 4);
+        assert_eq!(cpu.registers.y, 5);
+        assert_eq!(cpu.cycle, 2 + 4 + 1 + 2);
+    }
+
+    #[test]
+    fn reports_unknown_instructions() {
+        assert_eq!(
+            parse_input("noop\nsubx 3").unwrap_err().to_string(),
+            "Failed to parse instruction: 'subx 3'"
+        );
+        assert!(parse_input("addx three").is_err());
+        assert!(parse_input("noop 3").is_err());
+        assert_eq!(
+            signal_sum("noop\nsubx 3", &SAMPLE_CYCLES),
+            Err(ParseInstructionError(String::from("subx 3")))
+        );
+        assert!(render_crt("subx 3", &CrtConfig::default()).is_err());
+    }
+
+    #[test]
+    fn stops_endless_loops_at_the_cycle_limit() {
+        let mut cpu = Cpu::new(parse_input("addx 1\njmp -1").unwrap());
+        cpu.cycle_limit = Some(10);
+
+        let mut cycles_seen = 0;
+        assert_eq!(cpu.run(|_| cycles_seen += 1), StopReason::CycleLimit);
+        assert_eq!(cycles_seen, 10);
     }
 }
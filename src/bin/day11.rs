@@ -1,85 +1,474 @@
 // I learned today to use BigInts, and that while let Some(x) can be very handy 🎉
 // I also learned at the end that I didn't have to use BigInts :-D
 // BigInts were very handy - I could do all the same operations with them as regular u64 and u128s!
-
-use std::collections::{HashMap, VecDeque};
-use twentytwo::{print_solution, read_from_stdin};
+// Revisited: Items never interact, so every item can be followed on its own path through the monkeys.
+// An item that comes back to the same (monkey, worry) state is in a cycle, and the rest of the rounds can be skipped.
+// Revisited again: The BigInts are back as an option, next to u64 and u128 that report overflows instead of wrapping.
+
+use itertools::Itertools;
+use num_bigint::BigUint;
+use std::{
+    collections::HashMap,
+    error::Error,
+    fmt::{Debug, Display},
+    hash::Hash,
+};
+use twentytwo::{
+    print_solution, read_from_stdin,
+    util::{self, describe},
+};
 
 fn main() {
     let input = read_from_stdin();
+    let options = Options::from_args();
+
+    match options.worry_type {
+        WorryType::U64 => solve::<u64>(&input, &options),
+        WorryType::U128 => solve::<u128>(&input, &options),
+        WorryType::Big => solve::<BigUint>(&input, &options),
+    }
+}
+
+fn solve<W: Worry>(input: &str, options: &Options) {
+    if !options.report_rounds.is_empty() {
+        let simulator = Simulator::<W>::new(parse_input(input), ReduceMode::DivideByThree);
+        print_round_reports(&simulator, &options.report_rounds, RoundState::holdings_report);
+    }
 
     print_solution(
         11,
         1,
         "What is the level of monkey business after 20 rounds of stuff-slinging simian shenanigans?",
-        format!("{}", calculate_monkey_business(&input, 20, ReduceMode::DivideByThree)),
+        describe(monkey_business::<W>(input, 20, ReduceMode::DivideByThree)),
     );
 
     print_solution(
         11,
         2,
         "Starting again from the initial state in your puzzle input, what is the level of monkey business after 10000 rounds?",
-        format!("{}", calculate_monkey_business(&input, 10_000, ReduceMode::ModulusByModuliProduct))
+        describe(monkey_business::<W>(input, 10_000, ReduceMode::ModulusByModuliProduct))
     );
+
+    if !options.report_rounds.is_empty() {
+        let simulator = Simulator::<W>::new(parse_input(input), ReduceMode::ModulusByModuliProduct);
+        print_round_reports(&simulator, &options.report_rounds, RoundState::inspections_report);
+    }
+
+    if let Some(reduction) = options.custom_reduction {
+        println!("🎄🎄🎄🎄 Day 11, Custom run 🎄🎄🎄🎄");
+        println!(
+            "What is the level of monkey business after {} rounds when {}?",
+            options.custom_rounds, reduction
+        );
+        println!(
+            "{}\n",
+            describe(monkey_business::<W>(
+                input,
+                options.custom_rounds,
+                ReduceMode::Custom(reduction)
+            ))
+        );
+    }
+}
+
+#[derive(Clone, Copy)]
+enum WorryType {
+    U64,
+    U128,
+    Big,
+}
+
+struct Options {
+    /// Print the monkeys after these rounds, like `1,20,1000`
+    report_rounds: Vec<usize>,
+    /// `u64`, `u128` or `big`
+    worry_type: WorryType,
+    /// Also run the monkeys with `divide:<n>` or `modulus:<n>`
+    custom_reduction: Option<CustomReduction>,
+    /// The number of rounds to run with the custom reduction
+    custom_rounds: usize,
+}
+
+impl Options {
+    fn from_args() -> Self {
+        let args = util::args();
+
+        let report_rounds = match util::flag_value(&args, "--report-rounds") {
+            Some(rounds) => rounds
+                .split(',')
+                .map(|round| round.trim().parse::<usize>().expect("parse round to report"))
+                .collect(),
+            None => Vec::new(),
+        };
+
+        let worry_type = match util::flag_value(&args, "--worry") {
+            None | Some("u64") => WorryType::U64,
+            Some("u128") => WorryType::U128,
+            Some("big") => WorryType::Big,
+            Some(other) => panic!("unknown worry type '{}', expected u64, u128 or big", other),
+        };
+
+        let custom_reduction = util::flag_value(&args, "--reduce").map(|reduction| {
+            match reduction
+                .split_once(':')
+                .map(|(kind, value)| (kind, value.parse::<u64>()))
+            {
+                Some(("divide", Ok(divisor))) if divisor > 0 => CustomReduction::DivideBy(divisor),
+                Some(("modulus", Ok(modulus))) if modulus > 0 => CustomReduction::ModulusBy(modulus),
+                _ => panic!("unknown reduction '{}', expected divide:<n> or modulus:<n>", reduction),
+            }
+        });
+
+        let custom_rounds = util::parse_flag(&args, "--rounds").unwrap_or(10_000);
+
+        Options {
+            report_rounds,
+            worry_type,
+            custom_reduction,
+            custom_rounds,
+        }
+    }
+}
+
+fn print_round_reports<W: Worry>(
+    simulator: &Simulator<W>,
+    report_rounds: &[usize],
+    report: fn(&RoundState<W>) -> String,
+) {
+    let last_round = report_rounds.iter().max().copied().unwrap_or(0);
+
+    for state in simulator.rounds().take(last_round) {
+        match state {
+            Ok(state) if report_rounds.contains(&state.round) => println!("{}", report(&state)),
+            Ok(_) => {}
+            Err(error) => println!("{}\n", error),
+        }
+    }
 }
 
 enum ReduceMode {
     DivideByThree,
     ModulusByModuliProduct,
+    Custom(CustomReduction),
 }
 
-fn calculate_monkey_business(input: &str, iterations: usize, mode: ReduceMode) -> u64 {
-    let mut state = parse_input(input);
-    let monkey_count = state.len();
-    let monkey_moduli_product: u64 = state.iter().map(|s| s.1.test_value).product();
-
-    for _ in 0..iterations {
-        for current_monkey_id in 0..monkey_count {
-            let mut current_monkey = state.get(&current_monkey_id).expect("get monkey").clone();
-
-            while let Some(item) = current_monkey.take_item() {
-                let new_worry_level = match current_monkey.operator {
-                    Operator::Add => match current_monkey.operand2 {
-                        Operand::Value(value) => item + value,
-                        Operand::Old => item + item,
-                    },
-                    Operator::Multiply => match current_monkey.operand2 {
-                        Operand::Value(value) => item * value,
-                        Operand::Old => item * item,
-                    },
-                };
-
-                let worry_level_after_monkey_gets_bored = match mode {
-                    ReduceMode::DivideByThree => new_worry_level / 3,
-                    ReduceMode::ModulusByModuliProduct => new_worry_level % monkey_moduli_product,
-                };
-
-                let mut destination_monkey: MonkeyState =
-                    if worry_level_after_monkey_gets_bored % current_monkey.test_value == 0 {
-                        state
-                            .get(&current_monkey.target_when_true)
-                            .expect("get target monkey when true")
-                            .clone()
-                    } else {
-                        state
-                            .get(&current_monkey.target_when_false)
-                            .expect("get target monkey when false")
-                            .clone()
-                    };
-
-                destination_monkey.receive_item(worry_level_after_monkey_gets_bored);
-                state.insert(destination_monkey.id, destination_monkey);
-            }
+/// A user supplied way of keeping the worry levels manageable
+#[derive(Clone, Copy, Debug)]
+enum CustomReduction {
+    DivideBy(u64),
+    ModulusBy(u64),
+}
 
-            state.insert(current_monkey_id, current_monkey);
+impl Display for CustomReduction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CustomReduction::DivideBy(divisor) => write!(f, "dividing worry levels by {}", divisor),
+            CustomReduction::ModulusBy(modulus) => write!(f, "taking worry levels modulo {}", modulus),
         }
     }
+}
+
+fn monkey_business<W: Worry>(input: &str, rounds: usize, mode: ReduceMode) -> Result<u128, WorryOverflow> {
+    let simulator = Simulator::<W>::new(parse_input(input), mode);
 
-    let mut all_items_inspected: Vec<u64> = state.iter().map(|t| t.1.items_inspected).collect();
+    let mut all_items_inspected = simulator.count_inspections(rounds)?;
     all_items_inspected.sort();
     all_items_inspected.reverse();
 
-    all_items_inspected[0] * all_items_inspected[1]
+    // Over very many rounds the product no longer fits in a u64
+    Ok(all_items_inspected[0] as u128 * all_items_inspected[1] as u128)
+}
+
+/// The number type used for worry levels. Operations that don't fit in the type return None,
+/// so an overflow is reported instead of silently wrapping around.
+trait Worry: Clone + Debug + Display + Eq + Hash + From<u64> {
+    fn try_add(&self, other: &Self) -> Option<Self>;
+    fn try_multiply(&self, other: &Self) -> Option<Self>;
+    fn divide(&self, divisor: u64) -> Self;
+    fn remainder(&self, modulus: u64) -> Self;
+    fn is_divisible_by(&self, divisor: u64) -> bool;
+}
+
+impl Worry for u64 {
+    fn try_add(&self, other: &Self) -> Option<Self> {
+        self.checked_add(*other)
+    }
+
+    fn try_multiply(&self, other: &Self) -> Option<Self> {
+        self.checked_mul(*other)
+    }
+
+    fn divide(&self, divisor: u64) -> Self {
+        self / divisor
+    }
+
+    fn remainder(&self, modulus: u64) -> Self {
+        self % modulus
+    }
+
+    fn is_divisible_by(&self, divisor: u64) -> bool {
+        self.is_multiple_of(divisor)
+    }
+}
+
+impl Worry for u128 {
+    fn try_add(&self, other: &Self) -> Option<Self> {
+        self.checked_add(*other)
+    }
+
+    fn try_multiply(&self, other: &Self) -> Option<Self> {
+        self.checked_mul(*other)
+    }
+
+    fn divide(&self, divisor: u64) -> Self {
+        self / divisor as u128
+    }
+
+    fn remainder(&self, modulus: u64) -> Self {
+        self % modulus as u128
+    }
+
+    fn is_divisible_by(&self, divisor: u64) -> bool {
+        self.is_multiple_of(divisor as u128)
+    }
+}
+
+impl Worry for BigUint {
+    fn try_add(&self, other: &Self) -> Option<Self> {
+        Some(self + other)
+    }
+
+    fn try_multiply(&self, other: &Self) -> Option<Self> {
+        Some(self * other)
+    }
+
+    fn divide(&self, divisor: u64) -> Self {
+        self / divisor
+    }
+
+    fn remainder(&self, modulus: u64) -> Self {
+        self % modulus
+    }
+
+    fn is_divisible_by(&self, divisor: u64) -> bool {
+        (self % divisor) == BigUint::from(0_u64)
+    }
+}
+
+#[derive(Debug, PartialEq)]
+struct WorryOverflow {
+    monkey: usize,
+    worry: String,
+}
+
+impl Error for WorryOverflow {}
+
+impl Display for WorryOverflow {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Worry level overflowed when monkey {} inspected an item with worry level {}, try --worry u128 or --worry big.",
+            self.monkey, self.worry
+        )
+    }
+}
+
+/// Runs the monkeys, with every monkey stored at the index of its id.
+struct Simulator<W: Worry> {
+    monkeys: Vec<Monkey>,
+    mode: ReduceMode,
+    moduli_product: u64,
+    items: Vec<Vec<W>>,
+}
+
+impl<W: Worry> Simulator<W> {
+    fn new(monkeys: Vec<Monkey>, mode: ReduceMode) -> Self {
+        let moduli_product = monkeys.iter().map(|monkey| monkey.test_value).product();
+        let items = monkeys
+            .iter()
+            .map(|monkey| monkey.items.iter().map(|item| W::from(*item)).collect())
+            .collect();
+
+        Simulator {
+            monkeys,
+            mode,
+            moduli_product,
+            items,
+        }
+    }
+
+    /// Count how many items each monkey inspects during the given number of rounds
+    fn count_inspections(&self, rounds: usize) -> Result<Vec<u64>, WorryOverflow> {
+        let mut inspections = vec![0; self.monkeys.len()];
+
+        for (monkey_id, items) in self.items.iter().enumerate() {
+            for item in items.iter() {
+                let item_inspections = self.count_item_inspections(monkey_id, item.clone(), rounds)?;
+
+                for (total, count) in inspections.iter_mut().zip(item_inspections) {
+                    *total += count;
+                }
+            }
+        }
+
+        Ok(inspections)
+    }
+
+    /// Follow a single item through the rounds and count how many times each monkey inspects it.
+    /// The (monkey, worry) state at the start of every round is remembered, so when the item reaches
+    /// a state it has been in before, the inspections of the cycle are multiplied out instead of simulated.
+    fn count_item_inspections(&self, monkey_id: usize, worry: W, rounds: usize) -> Result<Vec<u64>, WorryOverflow> {
+        let mut seen_at_round: HashMap<(usize, W), usize> = HashMap::new();
+        // The accumulated inspections at the start of each round
+        let mut history: Vec<Vec<u64>> = vec![vec![0; self.monkeys.len()]];
+        let mut state = (monkey_id, worry);
+
+        for round in 0..rounds {
+            if let Some(&cycle_start) = seen_at_round.get(&state) {
+                let cycle_length = round - cycle_start;
+                let remaining_rounds = (rounds - round) as u64;
+                let full_cycles = remaining_rounds / cycle_length as u64;
+                let leftover_rounds = (remaining_rounds % cycle_length as u64) as usize;
+
+                return Ok((0..self.monkeys.len())
+                    .map(|id| {
+                        let per_cycle = history[round][id] - history[cycle_start][id];
+                        let leftover = history[cycle_start + leftover_rounds][id] - history[cycle_start][id];
+
+                        history[round][id] + per_cycle * full_cycles + leftover
+                    })
+                    .collect());
+            }
+
+            let mut inspections = history[round].clone();
+            let next_state = self.play_item_round(state.clone(), &mut inspections)?;
+            seen_at_round.insert(state, round);
+            state = next_state;
+            history.push(inspections);
+        }
+
+        Ok(history.pop().expect("history is never empty"))
+    }
+
+    /// Play one round for a single item, returning the monkey holding it and its worry level afterwards.
+    /// Monkeys take turns in order of their id, so an item thrown to a monkey with a higher id
+    /// is inspected again in the same round, while a throw to a lower id waits for the next round.
+    fn play_item_round(
+        &self,
+        (mut monkey_id, mut worry): (usize, W),
+        inspections: &mut [u64],
+    ) -> Result<(usize, W), WorryOverflow> {
+        loop {
+            let monkey = &self.monkeys[monkey_id];
+            inspections[monkey_id] += 1;
+
+            worry = self.reduce(monkey.inspect(&worry)?);
+            let target = monkey.throw_target(&worry);
+
+            if target <= monkey_id {
+                break Ok((target, worry));
+            }
+
+            monkey_id = target;
+        }
+    }
+
+    /// Play all the monkeys round by round, yielding the state after each round
+    fn rounds(&self) -> Rounds<'_, W> {
+        Rounds {
+            simulator: self,
+            state: RoundState {
+                round: 0,
+                items: self.items.clone(),
+                items_inspected: vec![0; self.monkeys.len()],
+            },
+            overflowed: false,
+        }
+    }
+
+    fn reduce(&self, worry: W) -> W {
+        match self.mode {
+            ReduceMode::DivideByThree => worry.divide(3),
+            ReduceMode::ModulusByModuliProduct => worry.remainder(self.moduli_product),
+            ReduceMode::Custom(CustomReduction::DivideBy(divisor)) => worry.divide(divisor),
+            ReduceMode::Custom(CustomReduction::ModulusBy(modulus)) => worry.remainder(modulus),
+        }
+    }
+}
+
+/// The items held by each monkey and how many items each monkey has inspected, indexed by monkey id
+#[derive(Clone, Debug, PartialEq)]
+struct RoundState<W: Worry> {
+    round: usize,
+    items: Vec<Vec<W>>,
+    items_inspected: Vec<u64>,
+}
+
+impl<W: Worry> RoundState<W> {
+    /// The worry levels of the items each monkey holds, in the same format as the puzzle text
+    fn holdings_report(&self) -> String {
+        let mut report = format!(
+            "After round {}, the monkeys are holding items with these worry levels:\n",
+            self.round
+        );
+
+        for (id, items) in self.items.iter().enumerate() {
+            report += &format!("Monkey {}: {}\n", id, items.iter().join(", "));
+        }
+
+        report
+    }
+
+    /// How many items each monkey has inspected, in the same format as the puzzle text
+    fn inspections_report(&self) -> String {
+        let mut report = format!("== After round {} ==\n", self.round);
+
+        for (id, items_inspected) in self.items_inspected.iter().enumerate() {
+            report += &format!("Monkey {} inspected items {} times.\n", id, items_inspected);
+        }
+
+        report
+    }
+}
+
+/// An endless iterator of rounds, where the items are moved between the monkeys in place.
+/// The iterator ends after yielding an overflow error.
+struct Rounds<'a, W: Worry> {
+    simulator: &'a Simulator<W>,
+    state: RoundState<W>,
+    overflowed: bool,
+}
+
+impl<W: Worry> Iterator for Rounds<'_, W> {
+    type Item = Result<RoundState<W>, WorryOverflow>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.overflowed {
+            return None;
+        }
+
+        for monkey in self.simulator.monkeys.iter() {
+            let items = std::mem::take(&mut self.state.items[monkey.id]);
+            self.state.items_inspected[monkey.id] += items.len() as u64;
+
+            for item in items {
+                match monkey.inspect(&item) {
+                    Ok(worry) => {
+                        let worry = self.simulator.reduce(worry);
+                        self.state.items[monkey.throw_target(&worry)].push(worry);
+                    }
+                    Err(error) => {
+                        self.overflowed = true;
+                        return Some(Err(error));
+                    }
+                }
+            }
+        }
+
+        self.state.round += 1;
+
+        Some(Ok(self.state.clone()))
+    }
 }
 
 #[derive(Clone)]
@@ -95,10 +484,9 @@ enum Operand {
 }
 
 #[derive(Clone)]
-struct MonkeyState {
+struct Monkey {
     id: usize,
-    items: VecDeque<u64>,
-    items_inspected: u64,
+    items: Vec<u64>,
     operator: Operator,
     operand2: Operand,
     test_value: u64,
@@ -106,37 +494,58 @@ struct MonkeyState {
     target_when_false: usize,
 }
 
-impl MonkeyState {
-    fn take_item(&mut self) -> Option<u64> {
-        if let Some(item) = self.items.pop_front() {
-            self.items_inspected += 1;
-            Some(item)
-        } else {
-            None
+impl Monkey {
+    /// The worry level of an item after this monkey has inspected it, before the worry is reduced
+    fn inspect<W: Worry>(&self, item: &W) -> Result<W, WorryOverflow> {
+        let operand = match self.operand2 {
+            Operand::Value(value) => W::from(value),
+            Operand::Old => item.clone(),
+        };
+
+        match self.operator {
+            Operator::Add => item.try_add(&operand),
+            Operator::Multiply => item.try_multiply(&operand),
         }
+        .ok_or_else(|| WorryOverflow {
+            monkey: self.id,
+            worry: format!("{}", item),
+        })
     }
 
-    fn receive_item(&mut self, item: u64) {
-        self.items.push_back(item)
+    fn throw_target<W: Worry>(&self, item: &W) -> usize {
+        if item.is_divisible_by(self.test_value) {
+            self.target_when_true
+        } else {
+            self.target_when_false
+        }
     }
 }
 
 // Parsing -->
 
-fn parse_input(input: &str) -> HashMap<usize, MonkeyState> {
-    input
-        .split("\n\n")
-        .map(parse_monkey_state)
-        .map(|state| (state.id, state))
-        .collect()
+fn parse_input(input: &str) -> Vec<Monkey> {
+    let monkeys: Vec<Monkey> = input.split("\n\n").map(parse_monkey).collect();
+
+    monkeys.iter().enumerate().for_each(|(index, monkey)| {
+        if monkey.id != index {
+            panic!("expected monkey {} but found monkey {}", index, monkey.id);
+        }
+    });
+
+    monkeys
 }
 
-fn parse_monkey_state(input: &str) -> MonkeyState {
+fn parse_monkey(input: &str) -> Monkey {
     let lines: Vec<&str> = input.lines().collect();
 
-    let id = lines[0][7..8].parse::<usize>().expect("parse id");
+    let id = lines[0]
+        .strip_prefix("Monkey ")
+        .and_then(|rest| rest.strip_suffix(':'))
+        .expect("match monkey id")
+        .parse::<usize>()
+        .expect("parse id");
 
-    let items: VecDeque<u64> = lines[1]
+    let items: Vec<u64> = lines[1]
         .strip_prefix("  Starting items: ")
         .expect("match starting items")
         .split(", ")
@@ -176,10 +585,9 @@ fn parse_monkey_state(input: &str) -> MonkeyState {
         .parse::<usize>()
         .expect("parse false target");
 
-    MonkeyState {
+    Monkey {
         id,
         items,
-        items_inspected: 0,
         operator,
         operand2,
         test_value,
@@ -190,7 +598,8 @@ fn parse_monkey_state(input: &str) -> MonkeyState {
 
 #[cfg(test)]
 mod tests {
-    use crate::{calculate_monkey_business, ReduceMode};
+    use crate::{monkey_business, parse_input, CustomReduction, ReduceMode, Simulator, WorryOverflow};
+    use num_bigint::BigUint;
 
     const PUZZLE_INPUT: &str = include_str!("../../puzzle_inputs/day11.txt");
 
@@ -226,32 +635,129 @@ Monkey 3:
     #[test]
     fn solves_p1_example() {
         assert_eq!(
-            calculate_monkey_business(EXAMPLE_1, 20, ReduceMode::DivideByThree),
-            10605
+            monkey_business::<u64>(EXAMPLE_1, 20, ReduceMode::DivideByThree),
+            Ok(10605)
         );
     }
 
     #[test]
-    fn solves_p1() {
+    fn solves_p2_example() {
         assert_eq!(
-            calculate_monkey_business(PUZZLE_INPUT, 20, ReduceMode::DivideByThree),
-            108240
+            monkey_business::<u64>(EXAMPLE_1, 10_000, ReduceMode::ModulusByModuliProduct),
+            Ok(2713310158)
         );
     }
 
     #[test]
-    fn solves_p2_example() {
+    fn solves_p2_for_a_billion_rounds() {
+        assert_eq!(
+            monkey_business::<u64>(EXAMPLE_1, 1_000_000_000, ReduceMode::ModulusByModuliProduct),
+            Ok(27142382184098982504)
+        );
+    }
+
+    #[test]
+    fn reports_the_items_held_after_a_round() {
+        let simulator = Simulator::<u64>::new(parse_input(EXAMPLE_1), ReduceMode::DivideByThree);
+        let state = simulator.rounds().next().unwrap().unwrap();
+
+        assert_eq!(
+            state.holdings_report(),
+            r#"After round 1, the monkeys are holding items with these worry levels:
+Monkey 0: 20, 23, 27, 26
+Monkey 1: 2080, 25, 167, 207, 401, 1046
+Monkey 2: 
+Monkey 3: 
+"#
+        );
+    }
+
+    #[test]
+    fn reports_the_items_inspected_after_a_round() {
+        let simulator = Simulator::<u64>::new(parse_input(EXAMPLE_1), ReduceMode::ModulusByModuliProduct);
+        let state = simulator.rounds().nth(19).unwrap().unwrap();
+
+        assert_eq!(
+            state.inspections_report(),
+            r#"== After round 20 ==
+Monkey 0 inspected items 99 times.
+Monkey 1 inspected items 97 times.
+Monkey 2 inspected items 8 times.
+Monkey 3 inspected items 103 times.
+"#
+        );
+    }
+
+    #[test]
+    fn rounds_agree_with_the_per_item_engine() {
+        let simulator = Simulator::<u64>::new(parse_input(PUZZLE_INPUT), ReduceMode::ModulusByModuliProduct);
+        let state = simulator.rounds().nth(9_999).unwrap().unwrap();
+
+        assert_eq!(Ok(state.items_inspected), simulator.count_inspections(10_000));
+    }
+
+    // Monkey 0 squares its item every round, so the worry level is 2^(2^n) after n rounds
+    const SQUARING_MONKEYS: &str = r#"Monkey 0:
+  Starting items: 2
+  Operation: new = old * old
+  Test: divisible by 2
+    If true: throw to monkey 0
+    If false: throw to monkey 0
+
+Monkey 1:
+  Starting items: 1
+  Operation: new = old * 1
+  Test: divisible by 1
+    If true: throw to monkey 1
+    If false: throw to monkey 1
+"#;
+
+    #[test]
+    fn reports_overflow_instead_of_wrapping() {
+        let mode = || ReduceMode::Custom(CustomReduction::DivideBy(1));
+
+        assert_eq!(monkey_business::<u64>(SQUARING_MONKEYS, 5, mode()), Ok(25));
         assert_eq!(
-            calculate_monkey_business(EXAMPLE_1, 10_000, ReduceMode::ModulusByModuliProduct),
-            2713310158
+            monkey_business::<u64>(SQUARING_MONKEYS, 6, mode()),
+            Err(WorryOverflow {
+                monkey: 0,
+                worry: String::from("4294967296")
+            })
         );
+        assert_eq!(monkey_business::<u128>(SQUARING_MONKEYS, 6, mode()), Ok(36));
+        assert!(monkey_business::<u128>(SQUARING_MONKEYS, 7, mode()).is_err());
+        assert_eq!(monkey_business::<BigUint>(SQUARING_MONKEYS, 12, mode()), Ok(144));
     }
 
     #[test]
-    fn solves_p2() {
+    fn reports_overflow_in_rounds() {
+        let simulator = Simulator::<u64>::new(
+            parse_input(SQUARING_MONKEYS),
+            ReduceMode::Custom(CustomReduction::DivideBy(1)),
+        );
+
+        let states: Vec<_> = simulator.rounds().take(10).collect();
+        assert_eq!(states.len(), 6);
+        assert!(states[5].is_err());
+    }
+
+    #[test]
+    fn solves_p1_with_every_worry_type() {
         assert_eq!(
-            calculate_monkey_business(PUZZLE_INPUT, 10_000, ReduceMode::ModulusByModuliProduct),
-            25712998901
+            monkey_business::<u128>(EXAMPLE_1, 20, ReduceMode::DivideByThree),
+            Ok(10605)
         );
+        assert_eq!(
+            monkey_business::<BigUint>(EXAMPLE_1, 20, ReduceMode::DivideByThree),
+            Ok(10605)
+        );
+    }
+
+    #[test]
+    fn solves_p2_example_with_a_custom_modulus() {
+        // 23 * 19 * 13 * 17, the same as the product of the moduli
+        let mode = ReduceMode::Custom(CustomReduction::ModulusBy(96577));
+
+        assert_eq!(monkey_business::<u64>(EXAMPLE_1, 10_000, mode), Ok(2713310158));
     }
 }
@@ -1,211 +1,364 @@
-use twentytwo::{print_solution, read_from_stdin};
-
-#[derive(Copy, Clone)]
-enum Choice {
-    Rock,
-    Paper,
-    Scissors,
-}
-
-impl TryFrom<u8> for Choice {
-    type Error = ();
-
-    fn try_from(value: u8) -> Result<Self, Self::Error> {
-        match value {
-            b'A' => Ok(Choice::Rock),
-            b'B' => Ok(Choice::Paper),
-            b'C' => Ok(Choice::Scissors),
-            b'X' => Ok(Choice::Rock),
-            b'Y' => Ok(Choice::Paper),
-            b'Z' => Ok(Choice::Scissors),
-            _ => Err(()),
+use std::{collections::HashSet, error::Error, fmt::Display};
+use twentytwo::{
+    print_solution, read_from_stdin,
+    util::{self, describe},
+};
+
+/// One move of the game, with the letters for it in the strategy guide and its score
+struct Move {
+    name: &'static str,
+    opponent_code: char,
+    own_code: char,
+    score: u64,
+}
+
+impl Move {
+    fn new(name: &'static str, opponent_code: char, own_code: char, score: u64) -> Self {
+        Move {
+            name,
+            opponent_code,
+            own_code,
+            score,
         }
     }
 }
 
-#[derive(Copy, Clone)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 enum Outcome {
     Lose,
     Draw,
     Win,
 }
 
-impl TryFrom<u8> for Outcome {
-    type Error = ();
+/// The points for losing, drawing and winning a round, and the letters for each outcome in the strategy guide
+struct OutcomeTable {
+    scores: [u64; 3],
+    codes: [char; 3],
+}
+
+impl OutcomeTable {
+    fn score(&self, outcome: Outcome) -> u64 {
+        self.scores[outcome as usize]
+    }
 
-    fn try_from(value: u8) -> Result<Self, Self::Error> {
-        match value {
-            b'X' => Ok(Outcome::Lose),
-            b'Y' => Ok(Outcome::Draw),
-            b'Z' => Ok(Outcome::Win),
-            _ => Err(()),
-        }
+    fn outcome_for_code(&self, code: char) -> Option<Outcome> {
+        [Outcome::Lose, Outcome::Draw, Outcome::Win]
+            .into_iter()
+            .find(|outcome| self.codes[*outcome as usize] == code)
     }
 }
 
-/// A full game with OpponentChoice, OwnChoice
-struct GameWithChoices(Choice, Choice);
+const PUZZLE_OUTCOMES: OutcomeTable = OutcomeTable {
+    scores: [0, 3, 6],
+    codes: ['X', 'Y', 'Z'],
+};
+
+/// The game as data: the moves, which move beats which, and how rounds are scored
+struct Rules {
+    moves: Vec<Move>,
+    /// Pairs of (winner, loser), by their position in `moves`
+    beats: HashSet<(usize, usize)>,
+    outcomes: OutcomeTable,
+}
 
-impl TryFrom<(u8, u8)> for GameWithChoices {
-    type Error = ();
+impl Rules {
+    /// Build the rules from the moves and a list of which move beats which, by name
+    fn new(moves: Vec<Move>, beats: &[(&str, &str)], outcomes: OutcomeTable) -> Self {
+        let position = |name: &str| {
+            moves
+                .iter()
+                .position(|candidate| candidate.name == name)
+                .unwrap_or_else(|| panic!("Unknown move {}", name))
+        };
+
+        let beats = beats
+            .iter()
+            .map(|(winner, loser)| (position(winner), position(loser)))
+            .collect();
+
+        Rules { moves, beats, outcomes }
+    }
 
-    fn try_from(value: (u8, u8)) -> Result<Self, Self::Error> {
-        let opponent_choice = Choice::try_from(value.0)?;
-        let own_choice = Choice::try_from(value.1)?;
+    fn rock_paper_scissors() -> Self {
+        Rules::new(
+            vec![
+                Move::new("Rock", 'A', 'X', 1),
+                Move::new("Paper", 'B', 'Y', 2),
+                Move::new("Scissors", 'C', 'Z', 3),
+            ],
+            &[("Rock", "Scissors"), ("Paper", "Rock"), ("Scissors", "Paper")],
+            PUZZLE_OUTCOMES,
+        )
+    }
 
-        Ok(GameWithChoices(opponent_choice, own_choice))
+    fn rock_paper_scissors_lizard_spock() -> Self {
+        Rules::new(
+            vec![
+                Move::new("Rock", 'A', 'V', 1),
+                Move::new("Paper", 'B', 'W', 2),
+                Move::new("Scissors", 'C', 'X', 3),
+                Move::new("Lizard", 'D', 'Y', 4),
+                Move::new("Spock", 'E', 'Z', 5),
+            ],
+            &[
+                ("Scissors", "Paper"),
+                ("Paper", "Rock"),
+                ("Rock", "Lizard"),
+                ("Lizard", "Spock"),
+                ("Spock", "Scissors"),
+                ("Scissors", "Lizard"),
+                ("Lizard", "Paper"),
+                ("Paper", "Spock"),
+                ("Spock", "Rock"),
+                ("Rock", "Scissors"),
+            ],
+            PUZZLE_OUTCOMES,
+        )
+    }
+
+    fn by_name(name: &str) -> Option<Self> {
+        match name {
+            "rps" => Some(Rules::rock_paper_scissors()),
+            "rpsls" => Some(Rules::rock_paper_scissors_lizard_spock()),
+            _ => None,
+        }
+    }
+
+    fn outcome(&self, own: usize, opponent: usize) -> Outcome {
+        if self.beats.contains(&(own, opponent)) {
+            Outcome::Win
+        } else if self.beats.contains(&(opponent, own)) {
+            Outcome::Lose
+        } else {
+            Outcome::Draw
+        }
+    }
+
+    fn score(&self, own: usize, opponent: usize) -> u64 {
+        self.moves[own].score + self.outcomes.score(self.outcome(own, opponent))
     }
-}
 
-/// A full game with OpponentChoice and the Game Outcome
-struct GameWithOutcome(Choice, Outcome);
+    /// The best scoring move that gives the outcome, if any move does
+    fn move_for_outcome(&self, opponent: usize, outcome: Outcome) -> Option<usize> {
+        (0..self.moves.len())
+            .filter(|own| self.outcome(*own, opponent) == outcome)
+            .max_by_key(|own| (self.moves[*own].score, std::cmp::Reverse(*own)))
+    }
 
-impl TryFrom<(u8, u8)> for GameWithOutcome {
-    type Error = ();
+    /// The move that scores the most against the opponent's move
+    fn best_move(&self, opponent: usize) -> usize {
+        (0..self.moves.len())
+            .max_by_key(|own| (self.score(*own, opponent), std::cmp::Reverse(*own)))
+            .expect("at least one move")
+    }
 
-    fn try_from(value: (u8, u8)) -> Result<Self, Self::Error> {
-        let opponent_choice = Choice::try_from(value.0)?;
-        let outcome = Outcome::try_from(value.1)?;
+    fn opponent_move(&self, code: char) -> Option<usize> {
+        self.moves.iter().position(|candidate| candidate.opponent_code == code)
+    }
 
-        Ok(GameWithOutcome(opponent_choice, outcome))
+    fn own_move(&self, code: char) -> Option<usize> {
+        self.moves.iter().position(|candidate| candidate.own_code == code)
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+struct ParseGuideError(String);
+
+impl Error for ParseGuideError {}
+
+impl Display for ParseGuideError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Failed to parse strategy guide line: '{}'", self.0)
     }
 }
 
 fn main() {
     let input = read_from_stdin();
+    let options = Options::from_args();
+    let rules = &options.rules;
 
     print_solution(
         2,
         1,
         "What would your total score be if everything goes exactly according to your strategy guide?",
-        format!("{}", total_score_from_strategy_guide_with_choice(&input)),
+        describe(total_score_from_strategy_guide_with_choice(&input, rules)),
     );
 
     print_solution(
         2,
         2,
         "Following the Elf's instructions for the second column, what would your total score be if everything goes exactly according to your strategy guide?",
-        format!("{}", total_score_from_strategy_guide_with_outcome(&input)),
+        describe(total_score_from_strategy_guide_with_outcome(&input, rules)),
     );
-}
 
-/// D2P1
-fn total_score_from_strategy_guide_with_choice(input: &str) -> u64 {
-    parse_input_with_own_choice(input)
-        .expect("parse input")
-        .iter()
-        .map(score_from_choices)
-        .sum()
-}
+    if options.optimal {
+        for opponent in 0..rules.moves.len() {
+            let own = rules.best_move(opponent);
+            println!(
+                "Against {} play {} for {} points",
+                rules.moves[opponent].name,
+                rules.moves[own].name,
+                rules.score(own, opponent)
+            );
+        }
 
-fn parse_input_with_own_choice(input: &str) -> Result<Vec<GameWithChoices>, ()> {
-    input.lines().map(parse_line_with_own_choice).collect()
+        println!(
+            "Total score: {}\n",
+            describe(total_score_with_optimal_strategy(&input, rules))
+        );
+    }
 }
 
-fn parse_line_with_own_choice(line: &str) -> Result<GameWithChoices, ()> {
-    let bytes = line.as_bytes();
-    GameWithChoices::try_from((bytes[0], bytes[2]))
+struct Options {
+    /// The game to play, `rps` or `rpsls`
+    rules: Rules,
+    /// Print the best move against each opponent move, and the score of always playing it
+    optimal: bool,
 }
 
-fn score_from_choices(round: &GameWithChoices) -> u64 {
-    let outcome = match (round.1, round.0) {
-        (Choice::Rock, Choice::Rock) => Outcome::Draw,
-        (Choice::Rock, Choice::Paper) => Outcome::Lose,
-        (Choice::Rock, Choice::Scissors) => Outcome::Win,
-        (Choice::Paper, Choice::Rock) => Outcome::Win,
-        (Choice::Paper, Choice::Paper) => Outcome::Draw,
-        (Choice::Paper, Choice::Scissors) => Outcome::Lose,
-        (Choice::Scissors, Choice::Rock) => Outcome::Lose,
-        (Choice::Scissors, Choice::Paper) => Outcome::Win,
-        (Choice::Scissors, Choice::Scissors) => Outcome::Draw,
-    };
+impl Options {
+    fn from_args() -> Self {
+        let args = util::args();
 
-    pick_score(round.1) + outcome_score(outcome)
-}
+        let rules = util::flag_value(&args, "--rules")
+            .map(|name| Rules::by_name(name).expect("--rules needs rps or rpsls"))
+            .unwrap_or_else(Rules::rock_paper_scissors);
 
-/// D2P2
-fn total_score_from_strategy_guide_with_outcome(input: &str) -> u64 {
-    parse_input_with_outcome(input)
-        .expect("parse input")
-        .iter()
-        .map(score_from_outcome)
-        .sum()
+        Options {
+            rules,
+            optimal: util::has_flag(&args, "--optimal"),
+        }
+    }
 }
 
-fn parse_input_with_outcome(input: &str) -> Result<Vec<GameWithOutcome>, ()> {
-    input.lines().map(parse_line_with_outcome).collect()
+/// D2P1
+fn total_score_from_strategy_guide_with_choice(input: &str, rules: &Rules) -> Result<u64, ParseGuideError> {
+    parse_guide(input, |line, opponent_code, code| {
+        let opponent = rules.opponent_move(opponent_code).ok_or_else(|| error(line))?;
+        let own = rules.own_move(code).ok_or_else(|| error(line))?;
+
+        Ok(rules.score(own, opponent))
+    })
 }
 
-fn parse_line_with_outcome(line: &str) -> Result<GameWithOutcome, ()> {
-    let bytes = line.as_bytes();
-    GameWithOutcome::try_from((bytes[0], bytes[2]))
+/// D2P2
+fn total_score_from_strategy_guide_with_outcome(input: &str, rules: &Rules) -> Result<u64, ParseGuideError> {
+    parse_guide(input, |line, opponent_code, code| {
+        let opponent = rules.opponent_move(opponent_code).ok_or_else(|| error(line))?;
+        let outcome = rules.outcomes.outcome_for_code(code).ok_or_else(|| error(line))?;
+        let own = rules.move_for_outcome(opponent, outcome).ok_or_else(|| error(line))?;
+
+        Ok(rules.score(own, opponent))
+    })
 }
 
-fn score_from_outcome(round: &GameWithOutcome) -> u64 {
-    let own_pick = match (round.0, round.1) {
-        (Choice::Rock, Outcome::Lose) => Choice::Scissors,
-        (Choice::Rock, Outcome::Draw) => Choice::Rock,
-        (Choice::Rock, Outcome::Win) => Choice::Paper,
-        (Choice::Paper, Outcome::Lose) => Choice::Rock,
-        (Choice::Paper, Outcome::Draw) => Choice::Paper,
-        (Choice::Paper, Outcome::Win) => Choice::Scissors,
-        (Choice::Scissors, Outcome::Lose) => Choice::Paper,
-        (Choice::Scissors, Outcome::Draw) => Choice::Scissors,
-        (Choice::Scissors, Outcome::Win) => Choice::Rock,
-    };
+/// The score when ignoring the second column, and always playing the best move against the opponent
+fn total_score_with_optimal_strategy(input: &str, rules: &Rules) -> Result<u64, ParseGuideError> {
+    parse_guide(input, |line, opponent_code, _| {
+        let opponent = rules.opponent_move(opponent_code).ok_or_else(|| error(line))?;
 
-    pick_score(own_pick) + outcome_score(round.1)
+        Ok(rules.score(rules.best_move(opponent), opponent))
+    })
 }
 
-fn outcome_score(outcome: Outcome) -> u64 {
-    match outcome {
-        Outcome::Lose => 0,
-        Outcome::Draw => 3,
-        Outcome::Win => 6,
-    }
+fn error(line: &str) -> ParseGuideError {
+    ParseGuideError(String::from(line))
 }
 
-fn pick_score(choice: Choice) -> u64 {
-    match choice {
-        Choice::Rock => 1,
-        Choice::Paper => 2,
-        Choice::Scissors => 3,
-    }
+/// Split every line into its two letters, score the round, and add up the scores
+fn parse_guide(
+    input: &str,
+    score_round: impl Fn(&str, char, char) -> Result<u64, ParseGuideError>,
+) -> Result<u64, ParseGuideError> {
+    input
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| {
+            let mut columns = line.split_whitespace().map(|column| {
+                let mut chars = column.chars();
+                match (chars.next(), chars.next()) {
+                    (Some(code), None) => Ok(code),
+                    _ => Err(error(line)),
+                }
+            });
+
+            match (columns.next(), columns.next(), columns.next()) {
+                (Some(opponent_code), Some(code), None) => score_round(line, opponent_code?, code?),
+                _ => Err(error(line)),
+            }
+        })
+        .sum()
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
-    const PUZZLE_INPUT: &str = include_str!("../../puzzle_inputs/day2.txt");
-
     #[test]
     fn solves_p1_example() {
         let example = "A Y\nB X\nC Z";
-        let score = total_score_from_strategy_guide_with_choice(example);
+        let score = total_score_from_strategy_guide_with_choice(example, &Rules::rock_paper_scissors());
 
-        assert_eq!(score, 15);
+        assert_eq!(score, Ok(15));
     }
 
     #[test]
     fn solves_p2_example() {
         let example = "A Y\nB X\nC Z";
-        let score = total_score_from_strategy_guide_with_outcome(example);
+        let score = total_score_from_strategy_guide_with_outcome(example, &Rules::rock_paper_scissors());
 
-        assert_eq!(score, 12);
+        assert_eq!(score, Ok(12));
     }
 
     #[test]
-    fn solves_p1() {
-        let score = total_score_from_strategy_guide_with_choice(PUZZLE_INPUT);
-
-        assert_eq!(score, 11767);
+    fn plays_rock_paper_scissors_lizard_spock() {
+        let rules = Rules::rock_paper_scissors_lizard_spock();
+
+        // Spock vaporizes rock, lizard poisons Spock, and rock crushes lizard
+        let example = "A Z\nE Y\nD V";
+        assert_eq!(
+            total_score_from_strategy_guide_with_choice(example, &rules),
+            Ok(5 + 6 + 4 + 6 + 1 + 6)
+        );
+
+        // Against rock, both paper and Spock win, and Spock scores more
+        assert_eq!(total_score_from_strategy_guide_with_outcome("A Z", &rules), Ok(5 + 6));
+        assert_eq!(
+            total_score_from_strategy_guide_with_outcome("A X\nA Y", &rules),
+            Ok(4 + 1 + 3)
+        );
     }
 
     #[test]
-    fn solves_p2() {
-        let score = total_score_from_strategy_guide_with_outcome(PUZZLE_INPUT);
+    fn finds_the_optimal_strategy() {
+        let rules = Rules::rock_paper_scissors();
+        let best: Vec<&str> = (0..3)
+            .map(|opponent| rules.moves[rules.best_move(opponent)].name)
+            .collect();
+
+        assert_eq!(best, vec!["Paper", "Scissors", "Rock"]);
+        assert_eq!(
+            total_score_with_optimal_strategy("A Y\nB X\nC Z", &rules),
+            Ok(8 + 9 + 7)
+        );
+
+        let rules = Rules::rock_paper_scissors_lizard_spock();
+        assert_eq!(rules.moves[rules.best_move(0)].name, "Spock");
+    }
 
-        assert_eq!(score, 13886);
+    #[test]
+    fn reports_invalid_lines() {
+        let rules = Rules::rock_paper_scissors();
+
+        assert_eq!(
+            total_score_from_strategy_guide_with_choice("A", &rules),
+            Err(ParseGuideError(String::from("A")))
+        );
+        assert_eq!(
+            total_score_from_strategy_guide_with_outcome("A Y\nD X", &rules),
+            Err(ParseGuideError(String::from("D X")))
+        );
+        assert!(total_score_from_strategy_guide_with_choice("AB Y", &rules).is_err());
+        assert!(total_score_from_strategy_guide_with_choice("A Y Z", &rules).is_err());
     }
 }
@@ -1,87 +1,208 @@
-use itertools::{Chunk, Itertools};
-use std::vec::IntoIter;
-use twentytwo::{print_solution, read_from_stdin};
+use std::{error::Error, fmt::Display};
+use twentytwo::{
+    print_solution, read_from_stdin,
+    util::{self, describe},
+};
 
 fn main() {
     let input = read_from_stdin();
+    let options = Options::from_args();
 
     print_solution(
         3,
         1,
         "Find the item type that appears in both compartments of each rucksack. What is the sum of the priorities of those item types?",
-        format!("{}", priority_sum_of_supplies(&input)),
+        describe(priority_sum_of_supplies(&input)),
     );
 
     print_solution(
         3,
         2,
         "Find the item type that corresponds to the badges of each three-Elf group. What is the sum of the priorities of those item types?",
-        format!("{}", priority_sum_of_badges(&input)),
+        describe(priority_sum_of_badges(&input, options.group_size)),
     );
+
+    if options.items {
+        match (items_in_both_compartments(&input), badges(&input, options.group_size)) {
+            (Ok(shared), Ok(badges)) => {
+                println!("Items in both compartments:");
+                shared.iter().for_each(|items| println!("{}", items.items()));
+                println!("\nBadges:");
+                badges.iter().for_each(|items| println!("{}", items.items()));
+                println!();
+            }
+            (Err(error), _) | (_, Err(error)) => println!("{}\n", error),
+        }
+    }
 }
 
-// D3P1
-fn priority_sum_of_supplies(input: &str) -> u64 {
-    parse_rucksacks(input)
-        .into_iter()
-        .map(split_rucksack)
-        .map(element_in_both_compartments)
-        .map(to_priority_score)
-        .map(u64::from)
-        .sum()
+struct Options {
+    /// The number of elves in a group
+    group_size: usize,
+    /// Print every shared item of each rucksack and every badge of each group
+    items: bool,
 }
 
-fn split_rucksack(rucksack: &[u8]) -> (&[u8], &[u8]) {
-    rucksack.split_at(rucksack.len() / 2)
+impl Options {
+    fn from_args() -> Self {
+        let args = util::args();
+
+        Options {
+            group_size: util::parse_flag(&args, "--group-size").unwrap_or(3),
+            items: util::has_flag(&args, "--items"),
+        }
+    }
 }
 
-fn element_in_both_compartments((compartment_a, compartment_b): (&[u8], &[u8])) -> u8 {
-    *compartment_a
+// D3P1
+fn priority_sum_of_supplies(input: &str) -> Result<u64, RucksackError> {
+    Ok(items_in_both_compartments(input)?
         .iter()
-        .find(|v: &&u8| compartment_b.contains(v))
-        .expect("Common element")
+        .map(ItemSet::priority_sum)
+        .sum())
+}
+
+/// The items in both compartments of each rucksack
+fn items_in_both_compartments(input: &str) -> Result<Vec<ItemSet>, RucksackError> {
+    input
+        .lines()
+        .enumerate()
+        .map(|(index, rucksack)| {
+            // Every item is a single byte once the whole rucksack is known to be valid, so it can be split in half
+            ItemSet::from_items(rucksack, index + 1)?;
+
+            if rucksack.len() % 2 != 0 {
+                return Err(RucksackError::OddLength { line: index + 1 });
+            }
+
+            let (compartment_a, compartment_b) = rucksack.split_at(rucksack.len() / 2);
+            let items = |compartment| ItemSet::from_items(compartment, index + 1);
+
+            Ok(items(compartment_a)?.intersection(items(compartment_b)?))
+        })
+        .collect()
 }
 
 // D3P2
-fn priority_sum_of_badges(input: &str) -> u64 {
-    parse_rucksacks(input)
-        .into_iter()
-        .chunks(3)
-        .into_iter()
-        .map(badge_element)
-        .map(to_priority_score)
-        .map(u64::from)
-        .sum()
+fn priority_sum_of_badges(input: &str, group_size: usize) -> Result<u64, RucksackError> {
+    Ok(badges(input, group_size)?.iter().map(ItemSet::priority_sum).sum())
 }
 
-fn badge_element(rucksack_chunk: Chunk<IntoIter<&[u8]>>) -> u8 {
-    match rucksack_chunk.collect_vec().as_slice() {
-        [a, b, c] => *a
-            .iter()
-            .find(|elem| b.contains(elem) && c.contains(elem))
-            .expect("Common badge"),
-        _ => panic!("Expected 3 rucksacks"),
+/// The items every rucksack in each group of `group_size` rucksacks has in common
+fn badges(input: &str, group_size: usize) -> Result<Vec<ItemSet>, RucksackError> {
+    if group_size == 0 {
+        return Err(RucksackError::EmptyGroup);
     }
+
+    let rucksacks = parse_rucksacks(input)?;
+
+    if rucksacks.len() % group_size != 0 {
+        return Err(RucksackError::IncompleteGroup {
+            rucksacks: rucksacks.len() % group_size,
+            group_size,
+        });
+    }
+
+    Ok(rucksacks
+        .chunks(group_size)
+        .map(|group| {
+            group
+                .iter()
+                .fold(ItemSet::ALL, |shared, rucksack| shared.intersection(*rucksack))
+        })
+        .collect())
 }
 
 // Common
-fn parse_rucksacks(input: &str) -> Vec<&[u8]> {
-    input.lines().map(parse_rucksack).collect()
+fn parse_rucksacks(input: &str) -> Result<Vec<ItemSet>, RucksackError> {
+    input
+        .lines()
+        .enumerate()
+        .map(|(index, rucksack)| ItemSet::from_items(rucksack, index + 1))
+        .collect()
 }
 
-fn parse_rucksack(input: &str) -> &[u8] {
-    input.as_bytes()
+/// A set of item types, where bit 0 is `a` and bit 51 is `Z`, so bit n is the item with priority n + 1
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct ItemSet(u64);
+
+impl ItemSet {
+    const ALL: ItemSet = ItemSet((1 << 52) - 1);
+
+    fn from_items(items: &str, line: usize) -> Result<Self, RucksackError> {
+        items.chars().try_fold(ItemSet(0), |set, item| {
+            let priority = u8::try_from(item)
+                .ok()
+                .and_then(to_priority_score)
+                .ok_or(RucksackError::UnknownItem { line, item })?;
+
+            Ok(ItemSet(set.0 | 1 << (priority - 1)))
+        })
+    }
+
+    fn intersection(self, other: ItemSet) -> ItemSet {
+        ItemSet(self.0 & other.0)
+    }
+
+    fn priorities(&self) -> impl Iterator<Item = u8> + '_ {
+        (1..=52).filter(|priority| self.0 & 1 << (priority - 1) != 0)
+    }
+
+    fn priority_sum(&self) -> u64 {
+        self.priorities().map(u64::from).sum()
+    }
+
+    fn items(&self) -> String {
+        self.priorities().map(to_item).collect()
+    }
 }
 
-fn to_priority_score(element: u8) -> u8 {
+fn to_priority_score(element: u8) -> Option<u8> {
     if (97..=122).contains(&element) {
         // Transform a-z to range 1-26
-        element - 96
+        Some(element - 96)
     } else if (65..=90).contains(&element) {
         // Transform A-Z to range 27-52
-        element - 38
+        Some(element - 38)
+    } else {
+        None
+    }
+}
+
+fn to_item(priority: u8) -> char {
+    if priority <= 26 {
+        (priority + 96) as char
     } else {
-        panic!("Unknown rucksack item");
+        (priority + 38) as char
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum RucksackError {
+    OddLength { line: usize },
+    UnknownItem { line: usize, item: char },
+    IncompleteGroup { rucksacks: usize, group_size: usize },
+    EmptyGroup,
+}
+
+impl Error for RucksackError {}
+
+impl Display for RucksackError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RucksackError::OddLength { line } => {
+                write!(f, "The rucksack on line {} can't be split into two compartments", line)
+            }
+            RucksackError::UnknownItem { line, item } => write!(f, "Unknown item '{}' on line {}", item, line),
+            RucksackError::IncompleteGroup { rucksacks, group_size } => {
+                write!(
+                    f,
+                    "The last group has {} rucksacks instead of {}",
+                    rucksacks, group_size
+                )
+            }
+            RucksackError::EmptyGroup => write!(f, "A group needs at least one rucksack"),
+        }
     }
 }
 
@@ -96,25 +217,73 @@ wMqvLMZHhHMvwLHjbvcjnnSBnvTQFn
 ttgJtRGJQctTZtZT
 CrZsJsPPZsGzwwsLwLmpwMDw"#;
 
-    const PUZZLE_INPUT: &str = include_str!("../../puzzle_inputs/day3.txt");
-
     #[test]
     fn solves_p1_example() {
-        assert_eq!(priority_sum_of_supplies(EXAMPLE), 157);
+        assert_eq!(priority_sum_of_supplies(EXAMPLE), Ok(157));
     }
 
     #[test]
-    fn solves_p1() {
-        assert_eq!(priority_sum_of_supplies(PUZZLE_INPUT), 8088);
+    fn solves_p2_example() {
+        assert_eq!(priority_sum_of_badges(EXAMPLE, 3), Ok(70));
     }
 
     #[test]
-    fn solves_p2_example() {
-        assert_eq!(priority_sum_of_badges(EXAMPLE), 70);
+    fn finds_every_shared_item() {
+        let shared: Vec<String> = items_in_both_compartments(EXAMPLE)
+            .unwrap()
+            .iter()
+            .map(ItemSet::items)
+            .collect();
+        assert_eq!(shared, vec!["p", "L", "P", "v", "t", "s"]);
+
+        let shared = items_in_both_compartments("abcDabcE").unwrap();
+        assert_eq!(shared[0].items(), "abc");
+        assert_eq!(priority_sum_of_supplies("abcDabcE"), Ok(6));
+    }
+
+    #[test]
+    fn groups_any_number_of_rucksacks() {
+        let badges_of = |group_size| -> Vec<String> {
+            badges(EXAMPLE, group_size)
+                .unwrap()
+                .iter()
+                .map(ItemSet::items)
+                .collect()
+        };
+
+        assert_eq!(badges_of(3), vec!["r", "Z"]);
+        assert_eq!(badges_of(6), vec![""]);
+        assert_eq!(badges_of(2), vec!["frsFM", "qvwBT", "GJZ"]);
+        assert_eq!(
+            priority_sum_of_badges(EXAMPLE, 4),
+            Err(RucksackError::IncompleteGroup {
+                rucksacks: 2,
+                group_size: 4
+            })
+        );
     }
 
     #[test]
-    fn solves_p2() {
-        assert_eq!(priority_sum_of_badges(PUZZLE_INPUT), 2522);
+    fn reports_invalid_rucksacks() {
+        assert_eq!(
+            priority_sum_of_supplies("abc"),
+            Err(RucksackError::OddLength { line: 1 })
+        );
+        assert_eq!(
+            priority_sum_of_supplies("ab\na1"),
+            Err(RucksackError::UnknownItem { line: 2, item: '1' })
+        );
+        assert_eq!(
+            priority_sum_of_badges("ab\ncd\n-f", 3),
+            Err(RucksackError::UnknownItem { line: 3, item: '-' })
+        );
+        assert_eq!(
+            priority_sum_of_supplies(
+                "ab
+xéyz"
+            ),
+            Err(RucksackError::UnknownItem { line: 2, item: 'é' })
+        );
+        assert_eq!(priority_sum_of_badges(EXAMPLE, 0), Err(RucksackError::EmptyGroup));
     }
 }
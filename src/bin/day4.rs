@@ -1,8 +1,6 @@
-#[macro_use]
-extern crate lazy_static;
-
-use regex::{Captures, Regex};
-use twentytwo::{print_solution, read_from_stdin};
+use itertools::Itertools;
+use std::{error::Error, fmt::Display, str::FromStr};
+use twentytwo::{print_solution, read_from_stdin, util};
 
 fn main() {
     let input = read_from_stdin();
@@ -20,9 +18,14 @@ fn main() {
         "In how many assignment pairs do the ranges overlap?",
         format!("{}", count_partial_overlaps(&input)),
     );
+
+    if util::has_flag(&util::args(), "--analyze") {
+        println!("{}", analyze(&input));
+    }
 }
 
-struct Range(u8, u8);
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Range(u32, u32);
 
 impl Range {
     fn contained_in_range(&self, other_range: &Range) -> bool {
@@ -32,57 +35,201 @@ impl Range {
     fn overlaps_with_range(&self, other_range: &Range) -> bool {
         !(other_range.0 > self.1 || other_range.1 < self.0)
     }
+
+    /// Overlapping or right next to each other, so the two ranges could be one
+    fn mergeable_with_range(&self, other_range: &Range) -> bool {
+        (other_range.0 as u64) <= self.1 as u64 + 1 && (self.0 as u64) <= other_range.1 as u64 + 1
+    }
+
+    fn overlap_with_range(&self, other_range: &Range) -> u64 {
+        if self.overlaps_with_range(other_range) {
+            self.1.min(other_range.1) as u64 - self.0.max(other_range.0) as u64 + 1
+        } else {
+            0
+        }
+    }
 }
 
-impl From<(u8, u8)> for Range {
-    fn from(input: (u8, u8)) -> Self {
+impl From<(u32, u32)> for Range {
+    fn from(input: (u32, u32)) -> Self {
         Range(input.0, input.1)
     }
 }
 
-// D4P1
-fn count_full_overlaps(input: &str) -> u64 {
+#[derive(Debug, PartialEq, Eq)]
+struct ParseAssignmentError(String);
+
+impl Error for ParseAssignmentError {}
+
+impl Display for ParseAssignmentError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Failed to parse section assignment: '{}'", self.0)
+    }
+}
+
+impl FromStr for Range {
+    type Err = ParseAssignmentError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let error = || ParseAssignmentError(String::from(value));
+        let (start, end) = value.trim().split_once('-').ok_or_else(error)?;
+        let start = start.parse::<u32>().map_err(|_| error())?;
+        let end = end.parse::<u32>().map_err(|_| error())?;
+
+        if start > end {
+            return Err(error());
+        }
+
+        Ok(Range::from((start, end)))
+    }
+}
+
+/// The section assignments of one line, which can have any number of elves
+struct Group(Vec<Range>);
+
+impl FromStr for Group {
+    type Err = ParseAssignmentError;
+
+    fn from_str(line: &str) -> Result<Self, Self::Err> {
+        line.split(',')
+            .map(Range::from_str)
+            .collect::<Result<Vec<Range>, _>>()
+            .map(Group)
+    }
+}
+
+impl Group {
+    fn pairs(&self) -> impl Iterator<Item = ((usize, &Range), (usize, &Range))> {
+        self.0.iter().enumerate().tuple_combinations()
+    }
+
+    /// How many sections at least one elf in the group covers
+    fn covered_sections(&self) -> u64 {
+        self.sections_covered_at_least(1)
+    }
+
+    /// How many sections more than one elf in the group covers
+    fn shared_sections(&self) -> u64 {
+        self.sections_covered_at_least(2)
+    }
+
+    /// Sweep over where the ranges start and end, adding up the sections where enough ranges are open
+    fn sections_covered_at_least(&self, elves: usize) -> u64 {
+        let events = self
+            .0
+            .iter()
+            .flat_map(|range| [(range.0 as u64, 1), (range.1 as u64 + 1, -1)])
+            .sorted();
+
+        let mut open: i64 = 0;
+        let mut previous = 0;
+        let mut covered = 0;
+
+        for (section, change) in events {
+            if open >= elves as i64 {
+                covered += section - previous;
+            }
+
+            open += change;
+            previous = section;
+        }
+
+        covered
+    }
+
+    /// The two elves, by their position on the line, whose assignments overlap the most, and by how much
+    fn largest_overlap(&self) -> Option<(usize, usize, u64)> {
+        self.pairs()
+            .map(|((i, r1), (j, r2))| (i, j, r1.overlap_with_range(r2)))
+            .filter(|(_, _, overlap)| *overlap > 0)
+            .max_by_key(|(i, j, overlap)| (*overlap, std::cmp::Reverse((*i, *j))))
+    }
+
+    /// The pairs of elves whose assignments overlap or touch, so they could be given to one elf
+    fn mergeable_pairs(&self) -> Vec<(usize, usize)> {
+        self.pairs()
+            .filter(|((_, r1), (_, r2))| r1.mergeable_with_range(r2))
+            .map(|((i, _), (j, _))| (i, j))
+            .collect()
+    }
+}
+
+fn parse_groups(input: &str) -> Result<Vec<Group>, ParseAssignmentError> {
     input
         .lines()
-        .map(to_ranges)
-        .filter(|(r1, r2)| r1.contained_in_range(r2) || r2.contained_in_range(r1))
+        .filter(|line| !line.trim().is_empty())
+        .map(Group::from_str)
+        .collect()
+}
+
+// D4P1
+fn count_full_overlaps(input: &str) -> u64 {
+    parse_groups(input)
+        .expect("parse assignments")
+        .iter()
+        .filter(|group| {
+            group
+                .pairs()
+                .any(|((_, r1), (_, r2))| r1.contained_in_range(r2) || r2.contained_in_range(r1))
+        })
         .count() as u64
 }
 
 // D4P2
 fn count_partial_overlaps(input: &str) -> u64 {
-    input
-        .lines()
-        .map(to_ranges)
-        .filter(|(r1, r2)| r1.overlaps_with_range(r2))
+    parse_groups(input)
+        .expect("parse assignments")
+        .iter()
+        .filter(|group| group.pairs().any(|((_, r1), (_, r2))| r1.overlaps_with_range(r2)))
         .count() as u64
 }
 
-fn to_ranges(line: &str) -> (Range, Range) {
-    lazy_static! {
-        static ref REGEX: Regex = Regex::new("^(\\d+)-(\\d+),(\\d+)-(\\d+)$").unwrap();
-    }
+/// One line per group with its covered and shared sections, its largest overlap and its mergeable pairs,
+/// followed by the totals. Elves are numbered from 1 in the order they are listed.
+fn analyze(input: &str) -> String {
+    let groups = match parse_groups(input) {
+        Ok(groups) => groups,
+        Err(error) => return format!("{}\n", error),
+    };
 
-    let captures = REGEX.captures(line).expect("regex match");
+    let mut report = String::new();
 
-    (
-        Range::from((capture_to_u8(&captures, 1), capture_to_u8(&captures, 2))),
-        Range::from((capture_to_u8(&captures, 3), capture_to_u8(&captures, 4))),
-    )
-}
+    for (line, group) in groups.iter().enumerate() {
+        let largest_overlap = match group.largest_overlap() {
+            Some((i, j, overlap)) => format!("elves {} and {} share {}", i + 1, j + 1, overlap),
+            None => String::from("none"),
+        };
+        let mergeable = group
+            .mergeable_pairs()
+            .iter()
+            .map(|(i, j)| format!("{}+{}", i + 1, j + 1))
+            .join(" ");
+
+        report += &format!(
+            "Line {}: covered {}, shared {}, largest overlap: {}, mergeable: {}\n",
+            line + 1,
+            group.covered_sections(),
+            group.shared_sections(),
+            largest_overlap,
+            if mergeable.is_empty() { "none" } else { &mergeable }
+        );
+    }
+
+    report += &format!(
+        "Total: covered {}, shared {}, mergeable pairs {}\n",
+        groups.iter().map(Group::covered_sections).sum::<u64>(),
+        groups.iter().map(Group::shared_sections).sum::<u64>(),
+        groups.iter().map(|group| group.mergeable_pairs().len()).sum::<usize>()
+    );
 
-fn capture_to_u8(captures: &Captures, index: usize) -> u8 {
-    captures
-        .get(index)
-        .unwrap()
-        .as_str()
-        .parse::<u8>()
-        .expect("parse capture to u8")
+    report
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::{count_full_overlaps, count_partial_overlaps};
+    use std::str::FromStr;
+
+    use crate::{analyze, count_full_overlaps, count_partial_overlaps, Group, ParseAssignmentError, Range};
 
     const EXAMPLE: &str = r#"2-4,6-8
 2-3,4-5
@@ -92,25 +239,57 @@ mod tests {
 2-6,4-8
 "#;
 
-    const PUZZLE_INPUT: &str = include_str!("../../puzzle_inputs/day4.txt");
-
     #[test]
     fn solves_p1_example() {
         assert_eq!(count_full_overlaps(EXAMPLE), 2);
     }
 
     #[test]
-    fn solves_p1() {
-        assert_eq!(count_full_overlaps(PUZZLE_INPUT), 550);
+    fn solves_p2_example() {
+        assert_eq!(count_partial_overlaps(EXAMPLE), 4);
     }
 
     #[test]
-    fn solves_p2_example() {
-        assert_eq!(count_partial_overlaps(EXAMPLE), 4);
+    fn counts_covered_and_shared_sections() {
+        let group = Group::from_str("1-10,5-15,12-20,30-30").unwrap();
+
+        assert_eq!(group.covered_sections(), 21);
+        assert_eq!(group.shared_sections(), 10);
+        assert_eq!(group.largest_overlap(), Some((0, 1, 6)));
+        assert_eq!(group.mergeable_pairs(), vec![(0, 1), (1, 2)]);
+    }
+
+    #[test]
+    fn analyzes_the_example() {
+        assert_eq!(
+            analyze(EXAMPLE),
+            r#"Line 1: covered 6, shared 0, largest overlap: none, mergeable: none
+Line 2: covered 4, shared 0, largest overlap: none, mergeable: 1+2
+Line 3: covered 5, shared 1, largest overlap: elves 1 and 2 share 1, mergeable: 1+2
+Line 4: covered 7, shared 5, largest overlap: elves 1 and 2 share 5, mergeable: 1+2
+Line 5: covered 3, shared 1, largest overlap: elves 1 and 2 share 1, mergeable: 1+2
+Line 6: covered 7, shared 3, largest overlap: elves 1 and 2 share 3, mergeable: 1+2
+Total: covered 32, shared 10, mergeable pairs 5
+"#
+        );
+    }
+
+    #[test]
+    fn handles_large_section_ids_and_many_elves() {
+        let input = "1-4000000000,3999999999-4294967295,4294967295-4294967295\n";
+        let group = Group::from_str(input.trim()).unwrap();
+
+        assert_eq!(group.covered_sections(), 4294967295);
+        assert_eq!(group.shared_sections(), 3);
+        assert_eq!(group.largest_overlap(), Some((0, 1, 2)));
+        assert_eq!(count_full_overlaps(input), 1);
+        assert_eq!(count_partial_overlaps("1-2,3-4,5-6\n1-2,3-4,4-6\n"), 1);
     }
 
     #[test]
-    fn solves_p2() {
-        assert_eq!(count_partial_overlaps(PUZZLE_INPUT), 931);
+    fn rejects_invalid_assignments() {
+        assert_eq!(Range::from_str("5-2"), Err(ParseAssignmentError(String::from("5-2"))));
+        assert!(Group::from_str("1-4294967296").is_err());
+        assert!(Group::from_str("1-2;3-4").is_err());
     }
 }
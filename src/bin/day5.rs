@@ -2,153 +2,432 @@
 extern crate lazy_static;
 
 use regex::Regex;
-use std::collections::HashMap;
-use twentytwo::{print_solution, read_from_stdin, util::regex_capture_to_u8};
+use std::{collections::HashMap, error::Error, fmt::Display};
+use twentytwo::{
+    print_solution, read_from_stdin,
+    util::{self, describe, parse_regex_capture},
+};
 
-type Stacks = HashMap<u8, Vec<char>>;
+/// A crate is labelled with one or more characters, like `[Z]` or `[AB]`
+type Crate = String;
 
-#[derive(PartialEq)]
-enum Crane {
-    CrateMover9000,
-    CrateMover9001,
+/// The stacks in the order of their labels, with the top crate last,
+/// so moving crates only touches the tops of the stacks
+#[derive(Debug)]
+struct Stacks {
+    crates: Vec<Vec<Crate>>,
+    /// The number of each stack in `crates`, and where each stack number is in `crates`
+    numbers: Vec<usize>,
+    positions: HashMap<usize, usize>,
+}
+
+impl Stacks {
+    fn position(&self, stack: usize) -> Result<usize, CratesError> {
+        self.positions
+            .get(&stack)
+            .copied()
+            .ok_or(CratesError::UnknownStack(stack))
+    }
+
+    /// Move crates from the top of one stack to another in place, and let the crane model arrange them
+    fn apply(&mut self, instruction: &Instruction, crane_model: &dyn CraneModel) -> Result<(), CratesError> {
+        let from = self.position(instruction.from_stack)?;
+        let to = self.position(instruction.to_stack)?;
+        let available = self.crates[from].len();
+
+        if instruction.amount > available {
+            return Err(CratesError::NotEnoughCrates {
+                stack: instruction.from_stack,
+                has: available,
+                wants: instruction.amount,
+            });
+        }
+
+        // Lifting crates off a stack and putting them back on it changes nothing, whatever the crane
+        if from == to {
+            return Ok(());
+        }
+
+        let split = available - instruction.amount;
+        let (source, destination) = two_stacks(&mut self.crates, from, to);
+        let placed = destination.len();
+        destination.extend(source.drain(split..));
+
+        crane_model.arrange(&mut self.crates[to][placed..]);
+
+        Ok(())
+    }
+
+    /// The stacks in the same format as the puzzle, like `[Z] [M] [P]`, with the labels below.
+    /// Every column is as wide as the widest crate.
+    fn render(&self) -> String {
+        let width = self
+            .crates
+            .iter()
+            .flatten()
+            .map(|label| label.chars().count() + 2)
+            .chain(self.numbers.iter().map(|number| number.to_string().len()))
+            .max()
+            .unwrap_or(3);
+        let height = self.crates.iter().map(|stack| stack.len()).max().unwrap_or(0);
+
+        let crate_lines = (0..height).rev().map(|level| {
+            self.crates
+                .iter()
+                .map(|stack| match stack.get(level) {
+                    Some(label) => format!("{:<width$}", format!("[{}]", label), width = width),
+                    None => " ".repeat(width),
+                })
+                .collect::<Vec<String>>()
+                .join(" ")
+        });
+
+        let label_line = self
+            .numbers
+            .iter()
+            .map(|number| format!("{:^width$}", number, width = width))
+            .collect::<Vec<String>>()
+            .join(" ");
+
+        crate_lines
+            .chain(std::iter::once(label_line))
+            .map(|line| line + "\n")
+            .collect()
+    }
+
+    fn top_crates(&self) -> String {
+        self.crates
+            .iter()
+            .filter_map(|stack| stack.last())
+            .map(|top| top.as_str())
+            .collect()
+    }
+}
+
+/// Borrow two different stacks at the same time
+fn two_stacks(crates: &mut [Vec<Crate>], first: usize, second: usize) -> (&mut Vec<Crate>, &mut Vec<Crate>) {
+    if first < second {
+        let (left, right) = crates.split_at_mut(second);
+        (&mut left[first], &mut right[0])
+    } else {
+        let (left, right) = crates.split_at_mut(first);
+        (&mut right[0], &mut left[second])
+    }
+}
+
+// Cranes -->
+
+/// How a crane puts down the crates it moves. `arrange` gets the moved crates in the order they had on
+/// the source stack, bottom first, and puts them in the order they end up in on the destination stack.
+trait CraneModel {
+    fn arrange(&self, crates: &mut [Crate]);
+}
+
+/// Moves one crate at a time, which reverses their order
+struct CrateMover9000;
+
+impl CraneModel for CrateMover9000 {
+    fn arrange(&self, crates: &mut [Crate]) {
+        crates.reverse();
+    }
+}
+
+/// Moves all of the crates at once
+struct CrateMover9001;
+
+impl CraneModel for CrateMover9001 {
+    fn arrange(&self, _crates: &mut [Crate]) {}
+}
+
+/// Lifts at most `crates_per_lift` crates at a time from the top. A limit of 1 is a CrateMover 9000.
+struct LimitedLift {
+    crates_per_lift: usize,
+}
+
+impl CraneModel for LimitedLift {
+    fn arrange(&self, crates: &mut [Crate]) {
+        // Reversing everything puts the lifts in the right order, and reversing each lift restores its order
+        crates.reverse();
+        crates.chunks_mut(self.crates_per_lift).for_each(|lift| lift.reverse());
+    }
+}
+
+/// Moves all of the crates at once, but the top `rotation` crates slide off and end up at the bottom
+struct RotatingCrane {
+    rotation: usize,
+}
+
+impl CraneModel for RotatingCrane {
+    fn arrange(&self, crates: &mut [Crate]) {
+        if !crates.is_empty() {
+            let rotation = self.rotation % crates.len();
+            crates.rotate_right(rotation);
+        }
+    }
+}
+
+/// Pick a crane model by name: `9000`, `9001`, `lift:<crates per lift>` or `rotate:<crates>`
+fn crane_model_by_name(name: &str) -> Option<Box<dyn CraneModel>> {
+    let number = |text: &str| text.parse::<usize>().ok();
+
+    match name.split_once(':') {
+        None if name == "9000" => Some(Box::new(CrateMover9000)),
+        None if name == "9001" => Some(Box::new(CrateMover9001)),
+        Some(("lift", crates_per_lift)) => number(crates_per_lift)
+            .filter(|crates_per_lift| *crates_per_lift > 0)
+            .map(|crates_per_lift| Box::new(LimitedLift { crates_per_lift }) as Box<dyn CraneModel>),
+        Some(("rotate", rotation)) => {
+            number(rotation).map(|rotation| Box::new(RotatingCrane { rotation }) as Box<dyn CraneModel>)
+        }
+        _ => None,
+    }
 }
 
 #[derive(Debug)]
 struct Instruction {
-    amount: u8,
-    from_stack: u8,
-    to_stack: u8,
+    amount: usize,
+    from_stack: usize,
+    to_stack: usize,
+}
+
+impl Display for Instruction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "move {} from {} to {}", self.amount, self.from_stack, self.to_stack)
+    }
 }
 
 impl TryFrom<&str> for Instruction {
-    type Error = ();
+    type Error = CratesError;
 
     fn try_from(value: &str) -> Result<Self, Self::Error> {
         lazy_static! {
             static ref REGEX: Regex = Regex::new("^move (\\d+) from (\\d+) to (\\d+)$").unwrap();
         }
 
-        let captures = REGEX.captures(value).expect("regex match");
+        let error = || CratesError::InvalidInstruction(value.to_string());
+        let captures = REGEX.captures(value.trim_end()).ok_or_else(error)?;
 
         Ok(Instruction {
-            amount: regex_capture_to_u8(&captures, 1),
-            from_stack: regex_capture_to_u8(&captures, 2),
-            to_stack: regex_capture_to_u8(&captures, 3),
+            amount: parse_regex_capture(&captures, 1).map_err(|_| error())?,
+            from_stack: parse_regex_capture(&captures, 2).map_err(|_| error())?,
+            to_stack: parse_regex_capture(&captures, 3).map_err(|_| error())?,
         })
     }
 }
 
+#[derive(Debug, PartialEq, Eq)]
+enum CratesError {
+    MissingInstructions,
+    InvalidStackLabel(String),
+    CrateNotOnAStack { line: usize, column: usize },
+    InvalidInstruction(String),
+    UnknownStack(usize),
+    NotEnoughCrates { stack: usize, has: usize, wants: usize },
+}
+
+impl Error for CratesError {}
+
+impl Display for CratesError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CratesError::MissingInstructions => write!(f, "Expected a blank line followed by the instructions"),
+            CratesError::InvalidStackLabel(label) => write!(f, "Stack label '{}' isn't a number", label),
+            CratesError::CrateNotOnAStack { line, column } => {
+                write!(
+                    f,
+                    "The crate on line {}, column {} isn't above a stack label",
+                    line, column
+                )
+            }
+            CratesError::InvalidInstruction(instruction) => write!(f, "Invalid instruction '{}'", instruction),
+            CratesError::UnknownStack(stack) => write!(f, "There is no stack {}", stack),
+            CratesError::NotEnoughCrates { stack, has, wants } => {
+                write!(
+                    f,
+                    "Can't move {} crates from stack {}, which only has {}",
+                    wants, stack, has
+                )
+            }
+        }
+    }
+}
+
 fn main() {
     let input = read_from_stdin();
+    let options = Options::from_args();
 
     print_solution(
         5,
         1,
         "After the rearrangement procedure completes, what crate ends up on top of each stack?",
-        which_crate_on_top_of_each_stack(&input, Crane::CrateMover9000),
+        describe(which_crate_on_top_of_each_stack(&input, &CrateMover9000)),
     );
 
     print_solution(
         5,
         2,
         "After the rearrangement procedure completes, what crate ends up on top of each stack? (using CrateMover 9001)",
-        which_crate_on_top_of_each_stack(&input, Crane::CrateMover9001),
+        describe(which_crate_on_top_of_each_stack(&input, &CrateMover9001)),
     );
+
+    if let Some(crane_model) = &options.crane {
+        println!(
+            "Using the {} crane: {}\n",
+            options.crane_name,
+            describe(which_crate_on_top_of_each_stack(&input, crane_model.as_ref()))
+        );
+    }
+
+    if options.steps {
+        let crane_model = options.crane.unwrap_or_else(|| Box::new(CrateMover9000));
+
+        match rearrangement_steps(&input, crane_model.as_ref()) {
+            Ok(steps) => steps.iter().for_each(|step| println!("{}", step)),
+            Err(error) => println!("{}\n", error),
+        }
+    }
 }
 
-// D5P1 + D5P2
-fn which_crate_on_top_of_each_stack(input: &str, crane_model: Crane) -> String {
-    let (stacks, instructions) = parse_input(input);
+struct Options {
+    crane_name: String,
+    /// Solve with another crane model as well, see `crane_model_by_name`
+    crane: Option<Box<dyn CraneModel>>,
+    /// Print the stacks after every move
+    steps: bool,
+}
+
+impl Options {
+    fn from_args() -> Self {
+        let args = util::args();
 
-    let final_stack_state = instructions.iter().fold(stacks, |mut acc, ins| {
-        let source_stack = acc.get(&ins.from_stack).unwrap();
-        let (lifted, new_source_stack) = source_stack.split_at(ins.amount as usize);
+        let crane_name = util::flag_value(&args, "--crane").map(String::from);
 
-        // Put the lifted crates at the top of the new destination stack
-        let mut new_destination_stack = lifted.to_vec();
+        let crane = crane_name.as_ref().map(|name| {
+            crane_model_by_name(name)
+                .unwrap_or_else(|| panic!("Unknown crane model '{}', try 9000, 9001, lift:2 or rotate:1", name))
+        });
 
-        // Only reverse the lifted crates in part 1
-        if crane_model == Crane::CrateMover9000 {
-            new_destination_stack.reverse();
+        Options {
+            crane_name: crane_name.unwrap_or_default(),
+            crane,
+            steps: util::has_flag(&args, "--steps"),
         }
+    }
+}
 
-        // Move all crates from the old destination to the new one
-        let mut destination_stack = acc.get(&ins.to_stack).unwrap().clone();
-        new_destination_stack.append(&mut destination_stack);
+// D5P1 + D5P2
+fn which_crate_on_top_of_each_stack(input: &str, crane_model: &dyn CraneModel) -> Result<String, CratesError> {
+    let (mut stacks, instructions) = parse_input(input)?;
+
+    for instruction in instructions.iter() {
+        stacks.apply(instruction, crane_model)?;
+    }
+
+    Ok(stacks.top_crates())
+}
 
-        acc.insert(ins.from_stack, new_source_stack.to_vec());
-        acc.insert(ins.to_stack, new_destination_stack);
-        acc
-    });
+/// The drawing of the stacks before the first move and after every move, each with a heading
+fn rearrangement_steps(input: &str, crane_model: &dyn CraneModel) -> Result<Vec<String>, CratesError> {
+    let (mut stacks, instructions) = parse_input(input)?;
+    let mut steps = vec![format!("Start:\n{}", stacks.render())];
 
-    let mut final_stacks = final_stack_state.iter().collect::<Vec<(&u8, &Vec<char>)>>();
-    final_stacks.sort_by(|(i1, _), (i2, _)| i1.cmp(i2));
+    for instruction in instructions.iter() {
+        stacks.apply(instruction, crane_model)?;
+        steps.push(format!("After {}:\n{}", instruction, stacks.render()));
+    }
 
-    final_stacks.iter().map(|(_, stack)| stack.first().unwrap()).collect()
+    Ok(steps)
 }
 
-fn parse_input(input: &str) -> (Stacks, Vec<Instruction>) {
-    let split_input = input.split("\n\n").collect::<Vec<&str>>();
+fn parse_input(input: &str) -> Result<(Stacks, Vec<Instruction>), CratesError> {
+    let input = input.replace("\r\n", "\n");
+    let (drawing, instructions) = input.split_once("\n\n").ok_or(CratesError::MissingInstructions)?;
 
-    let stacks = parse_stacks(split_input.first().expect("Read stacks"));
+    let stacks = parse_stacks(drawing)?;
 
-    let instructions = split_input
-        .get(1)
-        .expect("Read instructions")
+    let instructions = instructions
         .lines()
+        .filter(|line| !line.trim().is_empty())
         .map(Instruction::try_from)
-        .collect::<Result<Vec<Instruction>, ()>>()
-        .expect("Parse instructions");
+        .collect::<Result<Vec<Instruction>, CratesError>>()?;
 
-    (stacks, instructions)
+    Ok((stacks, instructions))
 }
 
-fn parse_stacks(stack_input: &str) -> HashMap<u8, Vec<char>> {
-    let character_map = character_map(stack_input);
-
-    left_rotate(&character_map)
-        .iter()
-        .filter(|s| !(s.contains('[') || s.contains(']') || s.trim().is_empty()))
-        .map(|s| s.trim().chars().rev().collect::<String>())
-        .map(|s| {
-            (
-                s[0..1].parse::<u8>().unwrap(),
-                s[1..].chars().rev().collect::<Vec<char>>(),
-            )
+/// Read the stack labels on the last line of the drawing, and put each crate on the stack whose label
+/// is below it. This works for any number of stacks and crates of any width.
+fn parse_stacks(drawing: &str) -> Result<Stacks, CratesError> {
+    let lines: Vec<&str> = drawing.lines().collect();
+    let (label_line, crate_lines) = lines.split_last().ok_or(CratesError::MissingInstructions)?;
+
+    let labels = tokens(label_line)
+        .map(|(start, end)| {
+            let label = &label_line[start..end];
+            label
+                .parse::<usize>()
+                .map(|number| (start..end, number))
+                .map_err(|_| CratesError::InvalidStackLabel(label.to_string()))
         })
-        .collect()
-}
+        .collect::<Result<Vec<_>, CratesError>>()?;
+
+    let mut crates: Vec<Vec<Crate>> = vec![Vec::new(); labels.len()];
+
+    // Going up the drawing from the bottom puts every crate on top of the ones below it
+    for (y, line) in crate_lines.iter().enumerate().rev() {
+        for (start, end) in tokens(line) {
+            let position = labels
+                .iter()
+                .position(|(columns, _)| columns.start < end && start < columns.end)
+                .ok_or(CratesError::CrateNotOnAStack {
+                    line: y + 1,
+                    column: start + 1,
+                })?;
+
+            let label = line[start..end].trim_start_matches('[').trim_end_matches(']');
+            crates[position].push(label.to_string());
+        }
+    }
 
-fn character_map(stack_input: &str) -> HashMap<(usize, usize), char> {
-    stack_input
-        .lines()
+    let numbers: Vec<usize> = labels.iter().map(|(_, number)| *number).collect();
+    let positions = numbers
+        .iter()
         .enumerate()
-        .flat_map(|(y, line)| line.chars().enumerate().map(move |(x, char)| ((x, y), char)))
-        .collect()
+        .map(|(position, number)| (*number, position))
+        .collect();
+
+    Ok(Stacks {
+        crates,
+        numbers,
+        positions,
+    })
 }
 
-fn get_dimensions(character_map: &HashMap<(usize, usize), char>) -> (usize, usize) {
-    let max_x = character_map.iter().map(|(t, _)| t.0).max().expect("Max x");
-    let max_y = character_map.iter().map(|(t, _)| t.1).max().expect("Max y");
-
-    (max_x, max_y)
-}
-
-fn left_rotate(character_map: &HashMap<(usize, usize), char>) -> Vec<String> {
-    let (max_x, max_y) = get_dimensions(character_map);
+/// The start and end byte of every run of characters that aren't spaces
+fn tokens(line: &str) -> impl Iterator<Item = (usize, usize)> + '_ {
+    line.char_indices()
+        .filter(move |(index, char)| {
+            !char.is_whitespace()
+                && line[..*index]
+                    .chars()
+                    .next_back()
+                    .is_none_or(|before| before.is_whitespace())
+        })
+        .map(move |(start, _)| {
+            let end = line[start..]
+                .find(char::is_whitespace)
+                .map(|length| start + length)
+                .unwrap_or(line.len());
 
-    (0..=max_x)
-        .map(|x| {
-            (0..=max_y)
-                .map(|y| character_map.get(&(x, y)).expect("get coord"))
-                .collect::<String>()
+            (start, end)
         })
-        .collect()
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::{which_crate_on_top_of_each_stack, Crane};
+    use crate::{
+        crane_model_by_name, parse_input, rearrangement_steps, which_crate_on_top_of_each_stack, CrateMover9000,
+        CrateMover9001, CratesError, LimitedLift, RotatingCrane,
+    };
 
     const EXAMPLE: &str = r#"    [D]    
 [N] [C]    
@@ -165,27 +444,194 @@ move 1 from 1 to 2
 
     #[test]
     fn solves_p1_example() {
-        assert_eq!(which_crate_on_top_of_each_stack(EXAMPLE, Crane::CrateMover9000), "CMZ");
+        assert_eq!(
+            which_crate_on_top_of_each_stack(EXAMPLE, &CrateMover9000),
+            Ok(String::from("CMZ"))
+        );
     }
 
     #[test]
-    fn solves_p1() {
+    fn solves_p2_example() {
         assert_eq!(
-            which_crate_on_top_of_each_stack(PUZZLE_INPUT, Crane::CrateMover9000),
-            "VRWBSFZWM"
+            which_crate_on_top_of_each_stack(EXAMPLE, &CrateMover9001),
+            Ok(String::from("MCD"))
         );
     }
 
     #[test]
-    fn solves_p2_example() {
-        assert_eq!(which_crate_on_top_of_each_stack(EXAMPLE, Crane::CrateMover9001), "MCD");
+    fn parses_more_than_nine_stacks_and_wide_crates() {
+        let input = r#"                                        [AB]
+[A] [B] [C] [D] [E] [F] [G] [H] [I] [J] [KL]
+ 1   2   3   4   5   6   7   8   9   10  11
+
+move 1 from 11 to 10
+move 1 from 2 to 11
+"#;
+
+        assert_eq!(
+            which_crate_on_top_of_each_stack(input, &CrateMover9000),
+            Ok(String::from("ACDEFGHIABB"))
+        );
+
+        let (stacks, _) = parse_input(input).unwrap();
+        assert_eq!(stacks.crates[stacks.positions[&11]], vec!["KL", "AB"]);
+        assert_eq!(stacks.crates.len(), 11);
+    }
+
+    #[test]
+    fn reports_errors() {
+        let too_many = EXAMPLE.replace("move 3 from 1 to 3", "move 4 from 1 to 3");
+        assert_eq!(
+            which_crate_on_top_of_each_stack(&too_many, &CrateMover9001),
+            Err(CratesError::NotEnoughCrates {
+                stack: 1,
+                has: 3,
+                wants: 4
+            })
+        );
+
+        let overflow = EXAMPLE.replace("move 1 from 2 to 1", "move 300 from 2 to 1");
+        assert_eq!(
+            which_crate_on_top_of_each_stack(&overflow, &CrateMover9000),
+            Err(CratesError::NotEnoughCrates {
+                stack: 2,
+                has: 3,
+                wants: 300
+            })
+        );
+
+        let unknown_stack = EXAMPLE.replace("move 1 from 1 to 2", "move 1 from 1 to 4");
+        assert_eq!(
+            which_crate_on_top_of_each_stack(&unknown_stack, &CrateMover9000),
+            Err(CratesError::UnknownStack(4))
+        );
+
+        let invalid = EXAMPLE.replace("move 1 from 1 to 2", "move 99999999999999999999 from 1 to 2");
+        assert_eq!(
+            which_crate_on_top_of_each_stack(&invalid, &CrateMover9000),
+            Err(CratesError::InvalidInstruction(String::from(
+                "move 99999999999999999999 from 1 to 2"
+            )))
+        );
+
+        let misplaced = EXAMPLE.replace("[Z] [M] [P]", "[Z] [M] [P] [Q]");
+        assert_eq!(
+            which_crate_on_top_of_each_stack(&misplaced, &CrateMover9000),
+            Err(CratesError::CrateNotOnAStack { line: 3, column: 13 })
+        );
+    }
+
+    /// Stacks of `height` crates, and moves that carry half of a stack back and forth between the stacks
+    fn generate_input(stacks: usize, height: usize, moves: usize) -> String {
+        let crate_line = vec!["[X]"; stacks].join(" ");
+        let label_line: String = (1..=stacks).map(|number| format!(" {}  ", number)).collect();
+        let mut input = vec![crate_line; height].join("\n") + "\n" + &label_line + "\n\n";
+
+        for i in 0..moves {
+            let from = i % stacks + 1;
+            let to = (i + 1) % stacks + 1;
+            input += &format!("move {} from {} to {}\n", height / 2, from, to);
+        }
+
+        input
     }
 
     #[test]
-    fn solves_p2() {
+    fn moves_many_crates_quickly() {
+        let input = generate_input(5, 100, 100_000);
+
         assert_eq!(
-            which_crate_on_top_of_each_stack(PUZZLE_INPUT, Crane::CrateMover9001),
-            "RBTWJWMCF"
+            which_crate_on_top_of_each_stack(&input, &CrateMover9001),
+            Ok(String::from("XXXXX"))
+        );
+        assert_eq!(
+            which_crate_on_top_of_each_stack(&input, &CrateMover9000),
+            Ok(String::from("XXXXX"))
+        );
+    }
+
+    #[test]
+    fn moves_crates_onto_the_same_stack() {
+        let input = EXAMPLE.replace("move 1 from 1 to 2", "move 3 from 3 to 3");
+
+        assert_eq!(
+            which_crate_on_top_of_each_stack(&input, &CrateMover9000),
+            Ok(String::from("MZ"))
+        );
+        assert_eq!(
+            which_crate_on_top_of_each_stack(&input, &CrateMover9001),
+            Ok(String::from("CD"))
+        );
+
+        let steps = rearrangement_steps(&input, &LimitedLift { crates_per_lift: 2 }).unwrap();
+        let stacks = |step: &str| step.split_once('\n').unwrap().1.to_string();
+        assert_eq!(stacks(&steps[3]), stacks(&steps[4]));
+    }
+
+    #[test]
+    fn limited_lift_cranes_are_between_the_crate_movers() {
+        for input in [EXAMPLE, PUZZLE_INPUT] {
+            let with = |crates_per_lift| which_crate_on_top_of_each_stack(input, &LimitedLift { crates_per_lift });
+
+            assert_eq!(with(1), which_crate_on_top_of_each_stack(input, &CrateMover9000));
+            assert_eq!(with(100), which_crate_on_top_of_each_stack(input, &CrateMover9001));
+        }
+
+        // Moving 3 crates two at a time: Z N D is put down as N D, then Z
+        assert_eq!(
+            which_crate_on_top_of_each_stack(EXAMPLE, &LimitedLift { crates_per_lift: 2 }),
+            Ok(String::from("MCZ"))
+        );
+    }
+
+    #[test]
+    fn rotating_cranes_move_the_top_crates_to_the_bottom() {
+        assert_eq!(
+            which_crate_on_top_of_each_stack(EXAMPLE, &RotatingCrane { rotation: 0 }),
+            which_crate_on_top_of_each_stack(EXAMPLE, &CrateMover9001)
+        );
+        assert_eq!(
+            which_crate_on_top_of_each_stack(EXAMPLE, &RotatingCrane { rotation: 1 }),
+            Ok(String::from("CMN"))
+        );
+    }
+
+    #[test]
+    fn picks_crane_models_by_name() {
+        for name in ["9000", "9001", "lift:3", "rotate:2"] {
+            assert!(crane_model_by_name(name).is_some(), "{}", name);
+        }
+
+        for name in ["9002", "lift:0", "lift:x", "rotate", "spin:1"] {
+            assert!(crane_model_by_name(name).is_none(), "{}", name);
+        }
+    }
+
+    #[test]
+    fn renders_every_step_like_the_puzzle() {
+        let steps = rearrangement_steps(EXAMPLE, &CrateMover9000).unwrap();
+        let (drawing, _) = EXAMPLE.split_once("\n\n").unwrap();
+
+        assert_eq!(steps.len(), 5);
+        assert_eq!(steps[0], format!("Start:\n{}\n", drawing));
+        assert_eq!(
+            steps[1],
+            r#"After move 1 from 2 to 1:
+[D]        
+[N] [C]    
+[Z] [M] [P]
+ 1   2   3 
+"#
+        );
+        assert_eq!(
+            steps[4],
+            r#"After move 1 from 1 to 2:
+        [Z]
+        [N]
+        [D]
+[C] [M] [P]
+ 1   2   3 
+"#
         );
     }
 }
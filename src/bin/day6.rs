@@ -1,49 +1,351 @@
-use itertools::Itertools;
-use twentytwo::{print_solution, read_from_stdin};
+use std::{
+    collections::VecDeque,
+    fmt::Display,
+    io::{self, BufReader, Bytes, Read},
+};
+use twentytwo::{
+    print_solution, read_from_stdin,
+    util::{self, describe},
+};
 
 fn main() {
     let input = read_from_stdin();
+    let options = Options::from_args();
 
     print_solution(
         6,
         1,
         "How many characters need to be processed before the first start-of-packet marker is detected?",
-        format!("{}", characters_before_marker_end(&input)),
+        describe(characters_before_marker_end(&input).ok_or("No marker found")),
     );
 
     print_solution(
         6,
         2,
         "How many characters need to be processed before the first start-of-message marker is detected?",
-        format!("{}", characters_before_message_marker(&input)),
+        describe(characters_before_message_marker(&input).ok_or("No marker found")),
     );
+
+    if let Some(window) = options.markers {
+        let markers = Markers::new(input.as_bytes(), window)
+            .collect::<io::Result<Vec<usize>>>()
+            .expect("read markers");
+
+        println!("{} markers of {} unique characters end at:", markers.len(), window);
+        markers.iter().for_each(|marker| println!("{}", marker));
+        println!();
+    }
+
+    if options.frames || options.stats {
+        let frames = Datastream::new(input.as_bytes())
+            .collect::<io::Result<Vec<Frame>>>()
+            .expect("read frames");
+
+        if options.frames {
+            frames.iter().for_each(|frame| println!("{}", frame));
+            println!();
+        }
+
+        if options.stats {
+            println!("{}\n", DatastreamStats::from_frames(&frames));
+        }
+    }
+}
+
+struct Options {
+    /// Print where every marker of this many unique characters ends
+    markers: Option<usize>,
+    /// Print every packet in the datastream
+    frames: bool,
+    /// Print statistics about the packets
+    stats: bool,
+}
+
+impl Options {
+    fn from_args() -> Self {
+        let args = util::args();
+
+        let markers = util::parse_flag(&args, "--markers").inspect(|window: &usize| {
+            assert!(*window > 0, "--markers needs a window size of at least 1");
+        });
+
+        Options {
+            markers,
+            frames: util::has_flag(&args, "--frames"),
+            stats: util::has_flag(&args, "--stats"),
+        }
+    }
+}
+
+const START_OF_PACKET: usize = 4;
+const START_OF_MESSAGE: usize = 14;
+
+// D6P1
+fn characters_before_marker_end(input: &str) -> Option<usize> {
+    first_marker(input.as_bytes(), START_OF_PACKET)
+}
+
+// D6P2
+fn characters_before_message_marker(input: &str) -> Option<usize> {
+    first_marker(input.as_bytes(), START_OF_MESSAGE)
+}
+
+/// Where the first marker of `window` unique characters ends, reading no further than that
+fn first_marker(reader: impl Read, window: usize) -> Option<usize> {
+    Markers::new(reader, window)
+        .next()
+        .map(|marker| marker.expect("read datastream"))
+}
+
+// Marker detection -->
+
+/// Slides a window over the datastream one character at a time. A frequency table and a count of
+/// the characters that occur more than once in the window make every step O(1), whatever the window size.
+struct MarkerDetector {
+    window: usize,
+    counts: [usize; 256],
+    repeated: usize,
+    buffer: VecDeque<u8>,
+    position: usize,
+}
+
+impl MarkerDetector {
+    fn new(window: usize) -> Self {
+        assert!(window > 0, "a marker is at least one character");
+
+        MarkerDetector {
+            window,
+            counts: [0; 256],
+            repeated: 0,
+            buffer: VecDeque::with_capacity(window + 1),
+            position: 0,
+        }
+    }
+
+    /// Add the next character, and return the number of characters processed if the window is now a marker
+    fn push(&mut self, byte: u8) -> Option<usize> {
+        self.counts[byte as usize] += 1;
+        if self.counts[byte as usize] == 2 {
+            self.repeated += 1;
+        }
+
+        self.buffer.push_back(byte);
+        self.position += 1;
+
+        if self.buffer.len() > self.window {
+            let oldest = self.buffer.pop_front().expect("a full window") as usize;
+
+            self.counts[oldest] -= 1;
+            if self.counts[oldest] == 1 {
+                self.repeated -= 1;
+            }
+        }
+
+        (self.buffer.len() == self.window && self.repeated == 0).then_some(self.position)
+    }
+
+    /// Forget the characters in the window, so the next marker can't overlap the previous one
+    fn clear(&mut self) {
+        self.counts = [0; 256];
+        self.repeated = 0;
+        self.buffer.clear();
+    }
+}
+
+/// An iterator over the end of every marker in a stream, including overlapping ones.
+/// Line breaks and other whitespace aren't part of the datastream and are skipped.
+struct Markers<R: Read> {
+    bytes: Bytes<BufReader<R>>,
+    detector: MarkerDetector,
+}
+
+impl<R: Read> Markers<R> {
+    fn new(reader: R, window: usize) -> Self {
+        Markers {
+            bytes: BufReader::new(reader).bytes(),
+            detector: MarkerDetector::new(window),
+        }
+    }
+}
+
+impl<R: Read> Iterator for Markers<R> {
+    type Item = io::Result<usize>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        for byte in self.bytes.by_ref() {
+            match byte {
+                Ok(byte) if byte.is_ascii_whitespace() => continue,
+                Ok(byte) => {
+                    if let Some(marker) = self.detector.push(byte) {
+                        return Some(Ok(marker));
+                    }
+                }
+                Err(error) => return Some(Err(error)),
+            }
+        }
+
+        None
+    }
+}
+
+// Packet decoding -->
+
+/// The characters between one start-of-packet marker and the next, or the end of the stream
+#[derive(Debug, PartialEq, Eq)]
+struct Frame {
+    /// Where the frame starts, which is where its start-of-packet marker ends
+    start: usize,
+    content: Vec<u8>,
+    /// Where the message starts in the content, which is where its start-of-message marker ends
+    message_start: Option<usize>,
+}
+
+impl Frame {
+    /// The message after the start-of-message marker, if the frame has one
+    fn payload(&self) -> Option<&[u8]> {
+        self.message_start
+            .map(|start| &self.content[start.min(self.content.len())..])
+    }
 }
 
-fn characters_before_marker_end(input: &str) -> usize {
-    count_until_unique_block_end(input, 4)
+impl Display for Frame {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let text = |bytes: &[u8]| String::from_utf8_lossy(bytes).into_owned();
+
+        match self.payload() {
+            Some(payload) => write!(f, "{}\t{}\tmessage: {}", self.start, text(&self.content), text(payload)),
+            None => write!(f, "{}\t{}", self.start, text(&self.content)),
+        }
+    }
 }
 
-fn characters_before_message_marker(input: &str) -> usize {
-    count_until_unique_block_end(input, 14)
+/// Splits a datastream into frames at every start-of-packet marker. Markers of the same kind don't overlap,
+/// so the next one is looked for after the end of the previous one. Anything before the first marker is noise.
+///
+/// Start-of-message markers are looked for in the whole stream, since every start-of-message marker also
+/// contains start-of-packet markers. A message belongs to the frame its marker ends in, and its payload is
+/// the rest of that frame.
+struct Datastream<R: Read> {
+    bytes: Bytes<BufReader<R>>,
+    packets: MarkerDetector,
+    messages: MarkerDetector,
+    current: Option<Frame>,
 }
 
-fn count_until_unique_block_end(input: &str, unique_count: usize) -> usize {
-    let (left_index, _) = input
-        .as_bytes()
-        .windows(unique_count)
-        .enumerate()
-        .find(|(_, bytes)| bytes.iter().unique().count() == unique_count)
-        .expect("First unique 4 chars");
+impl<R: Read> Datastream<R> {
+    fn new(reader: R) -> Self {
+        Datastream {
+            bytes: BufReader::new(reader).bytes(),
+            packets: MarkerDetector::new(START_OF_PACKET),
+            messages: MarkerDetector::new(START_OF_MESSAGE),
+            current: None,
+        }
+    }
+}
+
+impl<R: Read> Iterator for Datastream<R> {
+    type Item = io::Result<Frame>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        for byte in self.bytes.by_ref() {
+            let byte = match byte {
+                Ok(byte) if byte.is_ascii_whitespace() => continue,
+                Ok(byte) => byte,
+                Err(error) => return Some(Err(error)),
+            };
+
+            if let Some(frame) = self.current.as_mut() {
+                frame.content.push(byte);
+            }
+
+            let mut finished = None;
+
+            if let Some(marker_end) = self.packets.push(byte) {
+                self.packets.clear();
+
+                let next = Frame {
+                    start: marker_end,
+                    content: Vec::new(),
+                    message_start: None,
+                };
+
+                finished = self.current.replace(next).map(|mut frame| {
+                    // The marker that ends this frame belongs to the next one
+                    frame.content.truncate(frame.content.len() - START_OF_PACKET);
+                    frame
+                });
+            }
+
+            if self.messages.push(byte).is_some() {
+                self.messages.clear();
+
+                if let Some(frame) = self.current.as_mut() {
+                    frame.message_start = Some(frame.content.len());
+                }
+            }
+
+            if let Some(frame) = finished {
+                return Some(Ok(frame));
+            }
+        }
 
-    left_index + unique_count
+        self.current.take().map(Ok)
+    }
+}
+
+#[derive(Debug, PartialEq)]
+struct DatastreamStats {
+    packet_markers: usize,
+    message_markers: usize,
+    /// The mean distance from one start-of-packet marker to the next
+    average_gap: Option<f64>,
+    payload_bytes: usize,
+}
+
+impl DatastreamStats {
+    fn from_frames(frames: &[Frame]) -> Self {
+        let gaps: Vec<usize> = frames.windows(2).map(|pair| pair[1].start - pair[0].start).collect();
+
+        DatastreamStats {
+            packet_markers: frames.len(),
+            message_markers: frames.iter().filter(|frame| frame.message_start.is_some()).count(),
+            average_gap: (!gaps.is_empty()).then(|| gaps.iter().sum::<usize>() as f64 / gaps.len() as f64),
+            payload_bytes: frames
+                .iter()
+                .filter_map(|frame| frame.payload())
+                .map(|payload| payload.len())
+                .sum(),
+        }
+    }
+}
+
+impl Display for DatastreamStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "Start-of-packet markers: {}", self.packet_markers)?;
+        writeln!(f, "Start-of-message markers: {}", self.message_markers)?;
+
+        match self.average_gap {
+            Some(gap) => writeln!(f, "Average gap between packets: {:.2}", gap)?,
+            None => writeln!(f, "Average gap between packets: -")?,
+        }
+
+        write!(f, "Message payload bytes: {}", self.payload_bytes)
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::{characters_before_marker_end, characters_before_message_marker};
+    use std::io::{self, Read};
+
+    use crate::{
+        characters_before_marker_end, characters_before_message_marker, first_marker, Datastream, DatastreamStats,
+        Frame, Markers,
+    };
 
     const PUZZLE_INPUT: &str = include_str!("../../puzzle_inputs/day6.txt");
 
+    const ALPHABET: &str = "abcdefghijklmnopqrstuvwxyz";
+
     #[test]
     fn solves_p1_examples() {
         let input: Vec<(&str, usize)> = vec![
@@ -56,7 +358,7 @@ mod tests {
 
         input.iter().for_each(|(input, expected_result)| {
             let result = characters_before_marker_end(input);
-            assert_eq!(&result, expected_result);
+            assert_eq!(result, Some(*expected_result));
         });
     }
 
@@ -72,17 +374,112 @@ mod tests {
 
         input.iter().for_each(|(input, expected_result)| {
             let result = characters_before_message_marker(input);
-            assert_eq!(&result, expected_result);
+            assert_eq!(result, Some(*expected_result));
         });
     }
 
     #[test]
-    fn solves_p1() {
-        assert_eq!(characters_before_marker_end(PUZZLE_INPUT), 1833);
+    fn finds_markers_of_every_window_size_up_to_26() {
+        let input = format!("aaaa{}", ALPHABET);
+
+        assert_eq!(first_marker(input.as_bytes(), 1), Some(1));
+
+        for window in 2..=26 {
+            let markers: Vec<usize> = Markers::new(input.as_bytes(), window).map(Result::unwrap).collect();
+
+            assert_eq!(markers.first(), Some(&(4 + window)));
+            assert_eq!(markers, (4 + window..=30).collect::<Vec<usize>>());
+        }
+    }
+
+    #[test]
+    fn finds_no_marker() {
+        assert_eq!(characters_before_marker_end("abcabcabc"), None);
+        assert_eq!(characters_before_message_marker(""), None);
+        assert_eq!(first_marker(ALPHABET.as_bytes(), 27), None);
+        assert_eq!(Markers::new("aaaaaaaa".as_bytes(), 2).count(), 0);
+    }
+
+    #[test]
+    fn streams_markers_from_a_reader() {
+        // A million repeated characters, then a line break, before the first marker
+        let reader = io::repeat(b'z').take(1_000_000).chain("\nabcz".as_bytes());
+        let markers: Vec<usize> = Markers::new(reader, 4).map(Result::unwrap).collect();
+
+        assert_eq!(markers, vec![1_000_003, 1_000_004]);
+    }
+
+    fn frames(input: &str) -> Vec<Frame> {
+        Datastream::new(input.as_bytes()).map(Result::unwrap).collect()
+    }
+
+    #[test]
+    fn splits_frames_at_packet_markers() {
+        assert_eq!(
+            frames("zzzzzabccccccdefffff"),
+            vec![
+                Frame {
+                    start: 8,
+                    content: b"cccc".to_vec(),
+                    message_start: None
+                },
+                Frame {
+                    start: 16,
+                    content: b"ffff".to_vec(),
+                    message_start: None
+                },
+            ]
+        );
     }
 
+    // The start-of-message marker zabcdefghijklm ends in the third frame, which starts after the packet marker hijk
+    const MESSAGE: &str = "zzzzabcdefghijklmn\nnnnnn";
+
     #[test]
-    fn solves_p2() {
-        assert_eq!(characters_before_message_marker(PUZZLE_INPUT), 3425);
+    fn extracts_message_payloads() {
+        let frames = frames(MESSAGE);
+
+        assert_eq!(
+            frames.iter().map(|frame| frame.start).collect::<Vec<usize>>(),
+            vec![7, 11, 15]
+        );
+        assert_eq!(frames[0].payload(), None);
+        assert_eq!(frames[2].payload(), Some("nnnnnn".as_bytes()));
+        assert_eq!(frames[2].to_string(), "15\tlmnnnnnn\tmessage: nnnnnn");
+    }
+
+    #[test]
+    fn reports_statistics() {
+        assert_eq!(
+            DatastreamStats::from_frames(&frames(MESSAGE)),
+            DatastreamStats {
+                packet_markers: 3,
+                message_markers: 1,
+                average_gap: Some(4.0),
+                payload_bytes: 6
+            }
+        );
+
+        assert_eq!(
+            DatastreamStats::from_frames(&frames("aaaa")),
+            DatastreamStats {
+                packet_markers: 0,
+                message_markers: 0,
+                average_gap: None,
+                payload_bytes: 0
+            }
+        );
+    }
+
+    #[test]
+    fn decodes_the_puzzle() {
+        let frames = frames(PUZZLE_INPUT);
+
+        // The first packet starts after part 1's marker, and packets never overlap
+        assert_eq!(
+            frames.first().map(|frame| frame.start),
+            characters_before_marker_end(PUZZLE_INPUT)
+        );
+        assert!(frames.windows(2).all(|pair| pair[1].start >= pair[0].start + 4));
     }
 }
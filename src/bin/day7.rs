@@ -1,190 +1,831 @@
-use std::collections::HashMap;
-use twentytwo::{print_solution, read_from_stdin};
+//! Day 7
+//! The disk is an arena of nodes, where every node knows its parent and directories know their children.
+//! Node ids only ever grow from parent to child, so walking the arena backwards is a post-order walk,
+//! which lets all directory sizes be calculated in a single pass.
 
-pub type NodeName = String;
-pub type Path = Vec<String>;
-pub type Directory = Vec<Node>;
-
-// My filesystem consists of paths pointing to a directory, which is a list of nodes
-pub type Filesystem = HashMap<Path, Directory>;
-
-#[derive(Clone)]
-pub struct Node(pub NodeName, pub NodeType);
-
-#[derive(Clone)]
-pub enum NodeType {
-    Directory,
-    File(u64),
-}
+use std::{
+    collections::{HashMap, HashSet},
+    error::Error,
+    fmt::Display,
+    str::FromStr,
+};
+use twentytwo::{print_solution, read_from_stdin, util};
 
 fn main() {
     let input = read_from_stdin();
+    let options = Options::from_args();
 
     print_solution(
         7,
         1,
         "Find all of the directories with a total size of at most 100000. What is the sum of the total sizes of those directories?",
-        format!("{}", sum_of_directories(&input))
+        format!("{}", sum_of_directories(&input, options.threshold))
     );
 
     print_solution(
         7,
         2,
         "Find the smallest directory that, if deleted, would free up enough space on the filesystem to run the update. What is the total size of that directory?",
-        format!("{}", size_of_smallest_directory_to_delete(&input))
+        format!("{}", size_of_smallest_directory_to_delete(&input, &options.disk))
     );
+
+    if options.validate {
+        let anomalies = validate_transcript(&input);
+
+        if anomalies.is_empty() {
+            println!("The transcript looks fine.\n");
+        } else {
+            anomalies.iter().for_each(|anomaly| println!("{}", anomaly));
+            println!();
+        }
+    }
+
+    if options.tree || options.du || options.largest_files.is_some() || options.strategy.is_some() {
+        let filesystem = build_filesystem(&parse_commands(&input).expect("parse commands"));
+
+        if options.tree {
+            println!("{}", filesystem.tree());
+        }
+
+        if options.du {
+            println!("{}", format_sizes(&filesystem.directory_sizes()));
+        }
+
+        if let Some(count) = options.largest_files {
+            println!("{}", format_sizes(&filesystem.largest_files(count)));
+        }
+
+        if let Some(strategy) = options.strategy {
+            match filesystem.directories_to_delete(&options.disk, strategy) {
+                Some(deletions) => {
+                    let freed: u64 = deletions.iter().map(|(_, size)| size).sum();
+
+                    print!("{}", format_sizes(&deletions));
+                    println!("{}\ttotal\n", freed);
+                }
+                None => println!("No directories free up enough space.\n"),
+            }
+        }
+    }
 }
 
+struct Options {
+    /// Report anything suspicious in the transcript
+    validate: bool,
+    /// Print the reconstructed filesystem
+    tree: bool,
+    /// Print the size of every directory
+    du: bool,
+    /// Print this many of the largest files
+    largest_files: Option<usize>,
+    /// The largest directory size summed up in part 1
+    threshold: u64,
+    disk: Disk,
+    /// Print the directories this strategy would delete
+    strategy: Option<DeletionStrategy>,
+}
+
+impl Options {
+    fn from_args() -> Self {
+        let args = util::args();
+
+        Options {
+            validate: util::has_flag(&args, "--validate"),
+            tree: util::has_flag(&args, "--tree"),
+            du: util::has_flag(&args, "--du"),
+            largest_files: util::parse_flag(&args, "--largest"),
+            threshold: util::parse_flag(&args, "--threshold").unwrap_or(PUZZLE_THRESHOLD),
+            disk: Disk {
+                capacity: util::parse_flag(&args, "--capacity").unwrap_or(PUZZLE_DISK.capacity),
+                required_free_space: util::parse_flag(&args, "--required").unwrap_or(PUZZLE_DISK.required_free_space),
+            },
+            strategy: util::flag_value(&args, "--strategy").map(|strategy| strategy.parse().expect("parse strategy")),
+        }
+    }
+}
+
+/// One `size<tab>path` line per entry, like `du`
+fn format_sizes(sizes: &[(String, u64)]) -> String {
+    sizes
+        .iter()
+        .map(|(path, size)| format!("{}\t{}\n", size, path))
+        .collect()
+}
+
+/// Directories of at most this size count towards part 1
+const PUZZLE_THRESHOLD: u64 = 100_000;
+
+/// The device from the puzzle text
+const PUZZLE_DISK: Disk = Disk {
+    capacity: 70_000_000,
+    required_free_space: 30_000_000,
+};
+
 // D7P1
-fn sum_of_directories(input: &str) -> u64 {
+fn sum_of_directories(input: &str, threshold: u64) -> u64 {
     let commands = parse_commands(input).expect("parse commands");
-    let filesystem = build_filesystem(commands);
+    let filesystem = build_filesystem(&commands);
 
-    list_of_directory_sizes(&filesystem)
-        .iter()
-        .filter(|&size| size <= &100_000)
+    filesystem
+        .directories()
+        .map(|id| filesystem.node(id).size)
+        .filter(|&size| size <= threshold)
         .sum()
 }
 
 // D7P2
-fn size_of_smallest_directory_to_delete(input: &str) -> u64 {
+fn size_of_smallest_directory_to_delete(input: &str, disk: &Disk) -> u64 {
     let commands = parse_commands(input).expect("parse commands");
-    let filesystem = build_filesystem(commands);
+    let filesystem = build_filesystem(&commands);
 
-    let free_space = 70_000_000 - calculate_directory_size(&vec!["/".to_string()], &filesystem);
+    filesystem
+        .directories_to_delete(disk, DeletionStrategy::SmallestDirectory)
+        .expect("find dir")
+        .iter()
+        .map(|(_, size)| size)
+        .sum()
+}
 
-    let mut directory_sizes = list_of_directory_sizes(&filesystem);
-    directory_sizes.sort();
+// Deleting -------->
 
-    *directory_sizes
-        .iter()
-        .find(|size| *size + free_space >= 30_000_000)
-        .expect("find dir")
+#[derive(Clone, Copy, Debug)]
+pub struct Disk {
+    pub capacity: u64,
+    pub required_free_space: u64,
 }
 
-fn list_of_directory_sizes(filesystem: &Filesystem) -> Vec<u64> {
-    list_of_directories(filesystem)
-        .iter()
-        .map(|&key| calculate_directory_size(key, filesystem))
-        .collect()
+/// How to pick the directories to delete. Deleted directories are never nested in each other,
+/// and deleting the root directory counts as deleting one directory.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DeletionStrategy {
+    /// The smallest single directory that frees up enough space, like part 2
+    SmallestDirectory,
+    /// As few directories as possible, and then as few bytes as possible
+    FewestDirectories,
+    /// As few bytes as possible, and then as few directories as possible
+    FewestBytes,
 }
 
-fn list_of_directories(filesystem: &Filesystem) -> Vec<&Vec<String>> {
-    filesystem.keys().collect()
+impl FromStr for DeletionStrategy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "smallest" => Ok(DeletionStrategy::SmallestDirectory),
+            "fewest" => Ok(DeletionStrategy::FewestDirectories),
+            "min-bytes" => Ok(DeletionStrategy::FewestBytes),
+            _ => Err(format!(
+                "Unknown strategy '{}', expected smallest, fewest or min-bytes",
+                s
+            )),
+        }
+    }
 }
 
-fn calculate_directory_size(path: &Vec<String>, filesystem: &Filesystem) -> u64 {
-    let contents = filesystem.get(path).expect("get directory list");
+/// The best selection found so far while searching for directories to delete
+struct Selection {
+    ids: Vec<NodeId>,
+    bytes: u64,
+}
 
-    contents
-        .iter()
-        .map(|node| match node.1 {
-            NodeType::File(size) => size,
-            NodeType::Directory => {
-                let mut dir_path = path.clone();
-                dir_path.push(node.0.clone());
+impl Filesystem {
+    /// How many more bytes must be freed before the update fits on the disk
+    pub fn space_to_free(&self, disk: &Disk) -> u64 {
+        let free_space = disk.capacity.saturating_sub(self.nodes[ROOT].size);
+
+        disk.required_free_space.saturating_sub(free_space)
+    }
+
+    /// The path and size of the directories to delete to free up enough space,
+    /// or `None` if no selection is big enough
+    pub fn directories_to_delete(&self, disk: &Disk, strategy: DeletionStrategy) -> Option<Vec<(String, u64)>> {
+        let ids = self.select_deletions(disk, strategy)?;
+
+        Some(
+            ids.into_iter()
+                .map(|id| (self.path_of(id), self.nodes[id].size))
+                .collect(),
+        )
+    }
+
+    fn select_deletions(&self, disk: &Disk, strategy: DeletionStrategy) -> Option<Vec<NodeId>> {
+        let needed = self.space_to_free(disk);
+
+        if needed == 0 {
+            return Some(Vec::new());
+        }
+
+        match strategy {
+            DeletionStrategy::SmallestDirectory => self
+                .directories()
+                .filter(|id| self.nodes[*id].size >= needed)
+                .min_by_key(|id| self.nodes[*id].size)
+                .map(|id| vec![id]),
+            DeletionStrategy::FewestDirectories | DeletionStrategy::FewestBytes => {
+                let mut best = None;
+                self.search_deletions(strategy, needed, vec![ROOT], &mut Vec::new(), 0, &mut best);
 
-                calculate_directory_size(&dir_path, filesystem)
+                best.map(|selection: Selection| selection.ids)
             }
+        }
+    }
+
+    fn child_directories(&self, id: NodeId) -> Vec<NodeId> {
+        self.children(id)
+            .iter()
+            .copied()
+            .filter(|child| matches!(self.nodes[*child].node_type, NodeType::Directory(_)))
+            .collect()
+    }
+
+    /// Branch and bound over the directories in `frontier`: either delete the next directory as a whole,
+    /// or keep it and consider its subdirectories instead.
+    fn search_deletions(
+        &self,
+        strategy: DeletionStrategy,
+        needed: u64,
+        mut frontier: Vec<NodeId>,
+        chosen: &mut Vec<NodeId>,
+        bytes: u64,
+        best: &mut Option<Selection>,
+    ) {
+        if bytes >= needed {
+            let better = match best {
+                None => true,
+                Some(best) if strategy == DeletionStrategy::FewestDirectories => {
+                    (chosen.len(), bytes) < (best.ids.len(), best.bytes)
+                }
+                Some(best) => (bytes, chosen.len()) < (best.bytes, best.ids.len()),
+            };
+
+            if better {
+                *best = Some(Selection {
+                    ids: chosen.clone(),
+                    bytes,
+                });
+            }
+
+            return;
+        }
+
+        // Deleting everything left in the frontier is the most that can still be freed
+        let reachable: u64 = frontier.iter().map(|id| self.nodes[*id].size).sum();
+
+        let hopeless = bytes + reachable < needed
+            || best.as_ref().is_some_and(|best| match strategy {
+                DeletionStrategy::FewestDirectories => chosen.len() + 1 > best.ids.len(),
+                _ => bytes >= best.bytes || best.bytes == needed,
+            });
+
+        if hopeless {
+            return;
+        }
+
+        let id = match frontier.pop() {
+            Some(id) => id,
+            None => return,
+        };
+
+        chosen.push(id);
+        self.search_deletions(
+            strategy,
+            needed,
+            frontier.clone(),
+            chosen,
+            bytes + self.nodes[id].size,
+            best,
+        );
+        chosen.pop();
+
+        // Keeping the largest directory at the end finds big enough selections early, which prunes more
+        frontier.extend(self.child_directories(id));
+        frontier.sort_by_key(|id| self.nodes[*id].size);
+        self.search_deletions(strategy, needed, frontier, chosen, bytes, best);
+    }
+}
+
+pub type NodeId = usize;
+
+/// The root directory is always the first node
+pub const ROOT: NodeId = 0;
+
+#[derive(Clone, Debug)]
+pub struct Node {
+    pub name: String,
+    pub parent: Option<NodeId>,
+    pub node_type: NodeType,
+    /// The size of a file, or the total size of everything in a directory once the sizes are calculated
+    pub size: u64,
+}
+
+#[derive(Clone, Debug)]
+pub enum NodeType {
+    Directory(Vec<NodeId>),
+    File,
+}
+
+#[derive(Clone, Debug)]
+pub struct Filesystem {
+    nodes: Vec<Node>,
+}
+
+impl Filesystem {
+    pub fn new() -> Self {
+        Filesystem {
+            nodes: vec![Node {
+                name: String::from("/"),
+                parent: None,
+                node_type: NodeType::Directory(Vec::new()),
+                size: 0,
+            }],
+        }
+    }
+
+    pub fn node(&self, id: NodeId) -> &Node {
+        &self.nodes[id]
+    }
+
+    pub fn children(&self, id: NodeId) -> &[NodeId] {
+        match &self.nodes[id].node_type {
+            NodeType::Directory(children) => children,
+            NodeType::File => &[],
+        }
+    }
+
+    pub fn child(&self, id: NodeId, name: &str) -> Option<NodeId> {
+        self.children(id)
+            .iter()
+            .copied()
+            .find(|child| self.nodes[*child].name == name)
+    }
+
+    pub fn directories(&self) -> impl Iterator<Item = NodeId> + '_ {
+        (0..self.nodes.len()).filter(|id| matches!(self.nodes[*id].node_type, NodeType::Directory(_)))
+    }
+
+    /// Find a node from a path like `/a/e`, `..`, or `a/b` relative to the directory `from`
+    pub fn resolve(&self, from: NodeId, path: &str) -> Option<NodeId> {
+        let start = if path.starts_with('/') { ROOT } else { from };
+
+        path.split('/').try_fold(start, |id, component| match component {
+            "" | "." => Some(id),
+            ".." => Some(self.nodes[id].parent.unwrap_or(ROOT)),
+            name => self.child(id, name),
         })
-        .sum()
+    }
+
+    /// Like `resolve`, but directories that don't exist yet are created along the way.
+    /// Returns `None` if the path goes through a file.
+    pub fn resolve_or_create(&mut self, from: NodeId, path: &str) -> Option<NodeId> {
+        let start = if path.starts_with('/') { ROOT } else { from };
+
+        path.split('/').try_fold(start, |id, component| match component {
+            "" | "." => Some(id),
+            ".." => Some(self.nodes[id].parent.unwrap_or(ROOT)),
+            name => self.add_directory(id, name),
+        })
+    }
+
+    /// Add a directory, or return the existing directory with that name.
+    /// Returns `None` if there is a file with that name, or `parent` is a file.
+    pub fn add_directory(&mut self, parent: NodeId, name: &str) -> Option<NodeId> {
+        match self.child(parent, name) {
+            Some(id) => matches!(self.nodes[id].node_type, NodeType::Directory(_)).then_some(id),
+            None => self.add_node(parent, name, NodeType::Directory(Vec::new()), 0),
+        }
+    }
+
+    /// Add a file, or update the size of the existing file with that name.
+    /// Returns `None` if there is a directory with that name, or `parent` is a file.
+    pub fn add_file(&mut self, parent: NodeId, name: &str, size: u64) -> Option<NodeId> {
+        match self.child(parent, name) {
+            Some(id) if matches!(self.nodes[id].node_type, NodeType::File) => {
+                self.nodes[id].size = size;
+                Some(id)
+            }
+            Some(_) => None,
+            None => self.add_node(parent, name, NodeType::File, size),
+        }
+    }
+
+    /// Files can't have children, so nothing is added to a file
+    fn add_node(&mut self, parent: NodeId, name: &str, node_type: NodeType, size: u64) -> Option<NodeId> {
+        if !matches!(self.nodes[parent].node_type, NodeType::Directory(_)) {
+            return None;
+        }
+
+        let id = self.nodes.len();
+
+        self.nodes.push(Node {
+            name: name.to_string(),
+            parent: Some(parent),
+            node_type,
+            size,
+        });
+
+        if let NodeType::Directory(children) = &mut self.nodes[parent].node_type {
+            children.push(id);
+        }
+
+        Some(id)
+    }
+
+    /// Calculate the size of every directory in one pass. Every node is added after its parent,
+    /// so walking backwards visits all children before their parent.
+    pub fn calculate_sizes(&mut self) {
+        for id in self.directories().collect::<Vec<NodeId>>() {
+            self.nodes[id].size = 0;
+        }
+
+        for id in (1..self.nodes.len()).rev() {
+            let size = self.nodes[id].size;
+            let parent = self.nodes[id].parent.expect("only the root has no parent");
+            self.nodes[parent].size += size;
+        }
+    }
+
+    /// The size of the node at an absolute path, like `/a/e`
+    pub fn size_of(&self, path: &str) -> Option<u64> {
+        self.resolve(ROOT, path).map(|id| self.nodes[id].size)
+    }
+
+    pub fn files(&self) -> impl Iterator<Item = NodeId> + '_ {
+        (0..self.nodes.len()).filter(|id| matches!(self.nodes[*id].node_type, NodeType::File))
+    }
+
+    /// The filesystem in the same format as the puzzle text, with nodes in the order they were listed
+    pub fn tree(&self) -> String {
+        let mut lines = Vec::new();
+        self.tree_lines(ROOT, 0, &mut lines);
+
+        lines.into_iter().map(|line| line + "\n").collect()
+    }
+
+    fn tree_lines(&self, id: NodeId, depth: usize, lines: &mut Vec<String>) {
+        let node = &self.nodes[id];
+        let indent = "  ".repeat(depth);
+
+        match node.node_type {
+            NodeType::Directory(_) => lines.push(format!("{}- {} (dir)", indent, node.name)),
+            NodeType::File => lines.push(format!("{}- {} (file, size={})", indent, node.name, node.size)),
+        }
+
+        for child in self.children(id) {
+            self.tree_lines(*child, depth + 1, lines);
+        }
+    }
+
+    /// The path and total size of every directory, largest first, like `du | sort -rn`
+    pub fn directory_sizes(&self) -> Vec<(String, u64)> {
+        self.sorted_by_size(self.directories())
+    }
+
+    /// The path and size of the `count` largest files
+    pub fn largest_files(&self, count: usize) -> Vec<(String, u64)> {
+        let mut files = self.sorted_by_size(self.files());
+        files.truncate(count);
+
+        files
+    }
+
+    fn sorted_by_size(&self, ids: impl Iterator<Item = NodeId>) -> Vec<(String, u64)> {
+        let mut sizes: Vec<(String, u64)> = ids.map(|id| (self.path_of(id), self.nodes[id].size)).collect();
+        sizes.sort_by(|(path1, size1), (path2, size2)| size2.cmp(size1).then_with(|| path1.cmp(path2)));
+
+        sizes
+    }
+
+    /// The absolute path of a node, like `/a/e`
+    pub fn path_of(&self, id: NodeId) -> String {
+        let mut names = Vec::new();
+        let mut current = id;
+
+        while let Some(parent) = self.nodes[current].parent {
+            names.push(self.nodes[current].name.as_str());
+            current = parent;
+        }
+
+        names.reverse();
+        format!("/{}", names.join("/"))
+    }
+}
+
+impl Default for Filesystem {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 // Parsing -------->
 
-#[derive(Debug)]
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub enum CommandType {
     Cd(String),
     CdParentDir,
     Ls(Vec<LsLine>),
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub enum LsLine {
     Directory(String),
     File(String, u64),
 }
 
-fn parse_commands(input: &str) -> Result<Vec<CommandType>, ()> {
-    input
-        .trim()
-        .split("$ ")
-        .filter(|chunk| !chunk.is_empty())
-        .map(|chunk| chunk.trim())
-        .map(chunk_to_command)
-        .collect()
+impl LsLine {
+    fn name(&self) -> &str {
+        match self {
+            LsLine::Directory(name) | LsLine::File(name, _) => name,
+        }
+    }
 }
 
-fn chunk_to_command(chunk: &str) -> Result<CommandType, ()> {
-    if chunk.starts_with("ls") {
-        let ls_output = chunk
-            .lines()
-            .skip(1)
-            .map(parse_ls_output_line)
-            .collect::<Result<Vec<LsLine>, ()>>()
-            .expect("parse ls output");
-
-        Ok(CommandType::Ls(ls_output))
-    } else if chunk.starts_with("cd ..") {
-        Ok(CommandType::CdParentDir)
-    } else if let Some(chunk_without_prefix) = chunk.strip_prefix("cd ") {
-        Ok(CommandType::Cd(chunk_without_prefix.to_string()))
-    } else {
-        Err(())
+/// A command and the line of the transcript it was typed on
+#[derive(Debug)]
+pub struct Command {
+    pub line: usize,
+    pub command_type: CommandType,
+}
+
+/// Strictly parse the transcript, failing on the first line that can't be understood
+fn parse_commands(input: &str) -> Result<Vec<CommandType>, Anomaly> {
+    let (commands, anomalies) = parse_transcript(input);
+
+    match anomalies.into_iter().next() {
+        Some(anomaly) => Err(anomaly),
+        None => Ok(commands.into_iter().map(|command| command.command_type).collect()),
     }
 }
 
-fn parse_ls_output_line(line: &str) -> Result<LsLine, ()> {
-    let white_space_pos = line
-        .chars()
-        .enumerate()
-        .find(|(_, c)| *c == ' ')
-        .expect("find whitespace")
-        .0;
+/// Leniently parse the transcript line by line, skipping and reporting the lines that can't be understood
+fn parse_transcript(input: &str) -> (Vec<Command>, Vec<Anomaly>) {
+    let mut commands: Vec<Command> = Vec::new();
+    let mut anomalies = Vec::new();
+    // Output is only expected after a known ls, and is skipped after an unknown command
+    let mut expecting_output = false;
+    let mut skipping_output = false;
+
+    for (index, text) in input.lines().enumerate() {
+        let line = index + 1;
+
+        if text.trim().is_empty() {
+            continue;
+        }
+
+        if let Some(command) = text.strip_prefix("$ ") {
+            match parse_command(command.trim()) {
+                Some(command_type) => {
+                    expecting_output = matches!(command_type, CommandType::Ls(_));
+                    skipping_output = false;
+                    commands.push(Command { line, command_type });
+                }
+                None => {
+                    expecting_output = false;
+                    skipping_output = true;
+                    anomalies.push(Anomaly::UnknownCommand {
+                        line,
+                        command: command.to_string(),
+                    });
+                }
+            }
+        } else if skipping_output {
+            continue;
+        } else {
+            match (expecting_output, parse_ls_output_line(text), commands.last_mut()) {
+                (
+                    true,
+                    Some(ls_line),
+                    Some(Command {
+                        command_type: CommandType::Ls(ls_lines),
+                        ..
+                    }),
+                ) => ls_lines.push(ls_line),
+                _ => anomalies.push(Anomaly::InvalidOutput {
+                    line,
+                    output: text.to_string(),
+                }),
+            }
+        }
+    }
 
-    let (first, second) = line.split_at(white_space_pos);
-    let name = second[1..].to_string();
+    (commands, anomalies)
+}
 
-    if first == "dir" {
-        Ok(LsLine::Directory(name))
+fn parse_command(command: &str) -> Option<CommandType> {
+    if command == "ls" {
+        Some(CommandType::Ls(Vec::new()))
+    } else if command == "cd .." {
+        Some(CommandType::CdParentDir)
     } else {
-        let size = first.parse::<u64>().expect("parse file size");
-        Ok(LsLine::File(name, size))
+        command
+            .strip_prefix("cd ")
+            .filter(|path| !path.is_empty())
+            .map(|path| CommandType::Cd(path.to_string()))
     }
 }
 
-fn build_filesystem(commands: Vec<CommandType>) -> Filesystem {
-    commands
-        .iter()
-        .fold((Vec::new(), HashMap::new()), |(mut path, mut fs), cmd| match cmd {
-            CommandType::Cd(dir) => {
-                path.push(dir.clone());
-                (path, fs)
+fn parse_ls_output_line(line: &str) -> Option<LsLine> {
+    let (first, name) = line.split_once(' ')?;
+
+    if name.is_empty() {
+        None
+    } else if first == "dir" {
+        Some(LsLine::Directory(name.to_string()))
+    } else {
+        let size = first.parse::<u64>().ok()?;
+        Some(LsLine::File(name.to_string(), size))
+    }
+}
+
+// Validation -------->
+
+/// Something in the transcript that doesn't add up
+#[derive(Debug, PartialEq, Eq)]
+pub enum Anomaly {
+    UnknownCommand { line: usize, command: String },
+    InvalidOutput { line: usize, output: String },
+    CdIntoUnlistedDirectory { line: usize, path: String },
+    CdIntoFile { line: usize, path: String },
+    ConflictingLs { line: usize, path: String },
+    NameConflict { line: usize, path: String },
+    UnvisitedDirectory { path: String },
+}
+
+impl Anomaly {
+    /// The transcript line the anomaly is on, if it is on one
+    fn line(&self) -> Option<usize> {
+        match self {
+            Anomaly::UnknownCommand { line, .. }
+            | Anomaly::InvalidOutput { line, .. }
+            | Anomaly::CdIntoUnlistedDirectory { line, .. }
+            | Anomaly::CdIntoFile { line, .. }
+            | Anomaly::ConflictingLs { line, .. }
+            | Anomaly::NameConflict { line, .. } => Some(*line),
+            Anomaly::UnvisitedDirectory { .. } => None,
+        }
+    }
+}
+
+impl Error for Anomaly {}
+
+impl Display for Anomaly {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Anomaly::UnknownCommand { line, command } => write!(f, "Line {}: Unknown command '{}'", line, command),
+            Anomaly::InvalidOutput { line, output } => write!(f, "Line {}: Unexpected output '{}'", line, output),
+            Anomaly::CdIntoUnlistedDirectory { line, path } => {
+                write!(f, "Line {}: cd into {}, which was never listed", line, path)
+            }
+            Anomaly::CdIntoFile { line, path } => write!(f, "Line {}: cd into {}, which is a file", line, path),
+            Anomaly::ConflictingLs { line, path } => {
+                write!(f, "Line {}: ls of {} differs from an earlier ls", line, path)
+            }
+            Anomaly::NameConflict { line, path } => {
+                write!(f, "Line {}: {} is listed as both a file and a directory", line, path)
+            }
+            Anomaly::UnvisitedDirectory { path } => {
+                write!(f, "{} was never listed, so its size is unknown", path)
+            }
+        }
+    }
+}
+
+/// Replay the transcript and report everything suspicious, in the order it appears, followed by the
+/// directories that were never listed
+fn validate_transcript(input: &str) -> Vec<Anomaly> {
+    let (commands, mut anomalies) = parse_transcript(input);
+
+    let mut filesystem = Filesystem::new();
+    let mut current_directory = ROOT;
+    // Directories that showed up in the output of an ls, and the ls output of each directory
+    let mut listed: HashSet<NodeId> = HashSet::from([ROOT]);
+    let mut ls_outputs: HashMap<NodeId, HashSet<LsLine>> = HashMap::new();
+
+    for Command { line, command_type } in commands {
+        match command_type {
+            CommandType::Cd(path) => {
+                let mut target = if path.starts_with('/') { ROOT } else { current_directory };
+
+                for component in path.split('/') {
+                    target = match component {
+                        "" | "." => target,
+                        ".." => filesystem.node(target).parent.unwrap_or(ROOT),
+                        name => match filesystem.add_directory(target, name) {
+                            Some(child) => {
+                                if !listed.contains(&child) {
+                                    anomalies.push(Anomaly::CdIntoUnlistedDirectory {
+                                        line,
+                                        path: filesystem.path_of(child),
+                                    });
+                                }
+
+                                child
+                            }
+                            // Like a shell, a cd into a file fails and stays where it was
+                            None => {
+                                if let Some(file) = filesystem.child(target, name) {
+                                    anomalies.push(Anomaly::CdIntoFile {
+                                        line,
+                                        path: filesystem.path_of(file),
+                                    });
+                                }
+
+                                target = current_directory;
+                                break;
+                            }
+                        },
+                    };
+                }
+
+                current_directory = target;
             }
             CommandType::CdParentDir => {
-                path.pop();
-                (path, fs)
+                current_directory = filesystem.node(current_directory).parent.unwrap_or(ROOT);
             }
-            CommandType::Ls(node_list) => {
-                let node_list: Vec<Node> = node_list
-                    .iter()
-                    .map(|node| match node {
-                        LsLine::Directory(name) => Node(name.clone(), NodeType::Directory),
-                        LsLine::File(name, size) => Node(name.clone(), NodeType::File(*size)),
-                    })
-                    .collect();
+            CommandType::Ls(ls_lines) => {
+                let output: HashSet<LsLine> = ls_lines.iter().cloned().collect();
+
+                match ls_outputs.get(&current_directory) {
+                    Some(earlier_output) if *earlier_output != output => anomalies.push(Anomaly::ConflictingLs {
+                        line,
+                        path: filesystem.path_of(current_directory),
+                    }),
+                    _ => {}
+                }
+
+                for ls_line in ls_lines.iter() {
+                    let added = match ls_line {
+                        LsLine::Directory(name) => filesystem.add_directory(current_directory, name),
+                        LsLine::File(name, size) => filesystem.add_file(current_directory, name, *size),
+                    };
 
-                fs.insert(path.clone(), node_list);
+                    match (added, filesystem.child(current_directory, ls_line.name())) {
+                        (Some(id), _) => {
+                            listed.insert(id);
+                        }
+                        // The name is already taken by a node of the other type
+                        (None, Some(existing)) => anomalies.push(Anomaly::NameConflict {
+                            line,
+                            path: filesystem.path_of(existing),
+                        }),
+                        (None, None) => {}
+                    }
+                }
 
-                (path, fs)
+                ls_outputs.insert(current_directory, output);
             }
-        })
-        .1
+        }
+    }
+
+    for id in filesystem.directories() {
+        if !ls_outputs.contains_key(&id) {
+            anomalies.push(Anomaly::UnvisitedDirectory {
+                path: filesystem.path_of(id),
+            });
+        }
+    }
+
+    // Anomalies found while parsing come before those found while replaying, so put them back in line order.
+    // The sort is stable, and directories that were never listed aren't on any line, so they stay last.
+    anomalies.sort_by_key(|anomaly| anomaly.line().unwrap_or(usize::MAX));
+    anomalies
+}
+
+fn build_filesystem(commands: &[CommandType]) -> Filesystem {
+    let mut filesystem = Filesystem::new();
+    let mut current_directory = ROOT;
+
+    for command in commands {
+        match command {
+            CommandType::Cd(path) => {
+                // Like a shell, a cd into a file fails and stays where it was
+                if let Some(directory) = filesystem.resolve_or_create(current_directory, path) {
+                    current_directory = directory;
+                }
+            }
+            CommandType::CdParentDir => {
+                current_directory = filesystem.node(current_directory).parent.unwrap_or(ROOT);
+            }
+            CommandType::Ls(ls_lines) => {
+                for line in ls_lines {
+                    match line {
+                        LsLine::Directory(name) => filesystem.add_directory(current_directory, name),
+                        LsLine::File(name, size) => filesystem.add_file(current_directory, name, *size),
+                    };
+                }
+            }
+        }
+    }
+
+    filesystem.calculate_sizes();
+    filesystem
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::{size_of_smallest_directory_to_delete, sum_of_directories};
+    use crate::{
+        build_filesystem, format_sizes, parse_commands, size_of_smallest_directory_to_delete, sum_of_directories,
+        validate_transcript, Anomaly, DeletionStrategy, Disk, Filesystem, PUZZLE_DISK, PUZZLE_THRESHOLD, ROOT,
+    };
 
     const EXAMPLE: &str = r#"$ cd /
 $ ls
@@ -215,21 +856,319 @@ $ ls
 
     #[test]
     fn solves_p1_example() {
-        assert_eq!(sum_of_directories(EXAMPLE), 95437);
+        assert_eq!(sum_of_directories(EXAMPLE, PUZZLE_THRESHOLD), 95437);
     }
 
     #[test]
     fn solves_p2_example() {
-        assert_eq!(size_of_smallest_directory_to_delete(EXAMPLE), 24933642);
+        assert_eq!(size_of_smallest_directory_to_delete(EXAMPLE, &PUZZLE_DISK), 24933642);
     }
 
     #[test]
-    fn solves_p1() {
-        assert_eq!(sum_of_directories(PUZZLE_INPUT), 1989474);
+    fn looks_up_sizes_by_path() {
+        let filesystem = build_filesystem(&parse_commands(EXAMPLE).unwrap());
+
+        assert_eq!(filesystem.size_of("/a/e"), Some(584));
+        assert_eq!(filesystem.size_of("/a"), Some(94853));
+        assert_eq!(filesystem.size_of("/d"), Some(24933642));
+        assert_eq!(filesystem.size_of("/"), Some(48381165));
+        assert_eq!(filesystem.size_of("/a/e/i"), Some(584));
+        assert_eq!(filesystem.size_of("/a/x"), None);
     }
 
     #[test]
-    fn solves_p2() {
-        assert_eq!(size_of_smallest_directory_to_delete(PUZZLE_INPUT), 1111607);
+    fn resolves_cd_to_root_and_nested_paths() {
+        let input = r#"$ cd /
+$ cd a/b
+$ ls
+100 c.txt
+$ cd /
+$ ls
+dir a
+200 d.txt
+$ cd a/b/..
+$ ls
+dir b
+300 e.txt
+"#;
+        let filesystem = build_filesystem(&parse_commands(input).unwrap());
+
+        assert_eq!(filesystem.size_of("/a/b/c.txt"), Some(100));
+        assert_eq!(filesystem.size_of("/a/b"), Some(100));
+        assert_eq!(filesystem.size_of("/a"), Some(400));
+        assert_eq!(filesystem.size_of("/"), Some(600));
+        assert_eq!(filesystem.size_of("/d.txt"), Some(200));
+    }
+
+    #[test]
+    fn does_not_cd_into_files() {
+        let input = "$ cd /\n$ ls\n100 b\n$ cd b\n$ ls\n50 x\n";
+        let filesystem = build_filesystem(&parse_commands(input).unwrap());
+
+        assert_eq!(filesystem.size_of("/b"), Some(100));
+        assert_eq!(filesystem.size_of("/b/x"), None);
+        assert_eq!(filesystem.size_of("/x"), Some(50));
+        assert_eq!(
+            filesystem.tree(),
+            "- / (dir)\n  - b (file, size=100)\n  - x (file, size=50)\n"
+        );
+
+        let mut filesystem = Filesystem::new();
+        let b = filesystem.add_file(ROOT, "b", 100).unwrap();
+        assert_eq!(filesystem.add_directory(ROOT, "b"), None);
+        assert_eq!(filesystem.add_file(b, "x", 50), None);
+        assert_eq!(filesystem.resolve_or_create(ROOT, "b/c"), None);
+    }
+
+    #[test]
+    fn builds_paths_from_parent_links() {
+        let mut filesystem = Filesystem::new();
+        let e = filesystem.resolve_or_create(ROOT, "a/e").unwrap();
+
+        assert_eq!(filesystem.path_of(e), "/a/e");
+        assert_eq!(filesystem.path_of(ROOT), "/");
+        assert_eq!(filesystem.resolve(e, "../.."), Some(ROOT));
+    }
+
+    #[test]
+    fn prints_the_tree_like_the_puzzle() {
+        let filesystem = build_filesystem(&parse_commands(EXAMPLE).unwrap());
+
+        assert_eq!(
+            filesystem.tree(),
+            r#"- / (dir)
+  - a (dir)
+    - e (dir)
+      - i (file, size=584)
+    - f (file, size=29116)
+    - g (file, size=2557)
+    - h.lst (file, size=62596)
+  - b.txt (file, size=14848514)
+  - c.dat (file, size=8504156)
+  - d (dir)
+    - j (file, size=4060174)
+    - d.log (file, size=8033020)
+    - d.ext (file, size=5626152)
+    - k (file, size=7214296)
+"#
+        );
+    }
+
+    #[test]
+    fn lists_directory_and_file_sizes() {
+        let filesystem = build_filesystem(&parse_commands(EXAMPLE).unwrap());
+
+        assert_eq!(
+            format_sizes(&filesystem.directory_sizes()),
+            "48381165\t/\n24933642\t/d\n94853\t/a\n584\t/a/e\n"
+        );
+        assert_eq!(
+            filesystem.largest_files(2),
+            vec![(String::from("/b.txt"), 14848514), (String::from("/c.dat"), 8504156)]
+        );
+    }
+
+    #[test]
+    fn finds_no_anomalies_in_the_puzzle() {
+        assert_eq!(validate_transcript(EXAMPLE), vec![]);
+        assert_eq!(validate_transcript(PUZZLE_INPUT), vec![]);
+    }
+
+    #[test]
+    fn reports_anomalies() {
+        let input = r#"$ cd /
+$ ls
+dir a
+100 b
+$ rm -rf a
+$ ls
+dir a
+200 b
+nonsense
+$ cd c
+$ ls
+dir d
+$ cd /a
+$ ls
+dir x
+300 x
+"#;
+
+        assert_eq!(
+            validate_transcript(input),
+            vec![
+                Anomaly::UnknownCommand {
+                    line: 5,
+                    command: String::from("rm -rf a")
+                },
+                Anomaly::ConflictingLs {
+                    line: 6,
+                    path: String::from("/")
+                },
+                Anomaly::InvalidOutput {
+                    line: 9,
+                    output: String::from("nonsense")
+                },
+                Anomaly::CdIntoUnlistedDirectory {
+                    line: 10,
+                    path: String::from("/c")
+                },
+                Anomaly::NameConflict {
+                    line: 14,
+                    path: String::from("/a/x")
+                },
+                Anomaly::UnvisitedDirectory {
+                    path: String::from("/c/d")
+                },
+                Anomaly::UnvisitedDirectory {
+                    path: String::from("/a/x")
+                },
+            ]
+        );
+
+        assert_eq!(
+            parse_commands(input).unwrap_err().to_string(),
+            "Line 5: Unknown command 'rm -rf a'"
+        );
+    }
+
+    #[test]
+    fn reports_cd_into_a_file() {
+        let input = "$ cd /\n$ ls\n100 b\n$ cd b\n$ ls\n100 b\n";
+
+        assert_eq!(
+            validate_transcript(input),
+            vec![Anomaly::CdIntoFile {
+                line: 4,
+                path: String::from("/b")
+            }]
+        );
+        assert_eq!(
+            validate_transcript(input)[0].to_string(),
+            "Line 4: cd into /b, which is a file"
+        );
+    }
+
+    #[test]
+    fn takes_the_puzzle_parameters_as_arguments() {
+        assert_eq!(sum_of_directories(EXAMPLE, 1000), 584);
+
+        let disk = Disk {
+            capacity: 50_000_000,
+            required_free_space: 1_700_000,
+        };
+        assert_eq!(size_of_smallest_directory_to_delete(EXAMPLE, &disk), 94853);
+
+        let disk = Disk {
+            capacity: 48_500_000,
+            required_free_space: 119_000,
+        };
+        assert_eq!(size_of_smallest_directory_to_delete(EXAMPLE, &disk), 584);
+
+        // Only deleting everything frees up enough space
+        let disk = Disk {
+            capacity: 48_381_165,
+            required_free_space: 48_000_000,
+        };
+        assert_eq!(size_of_smallest_directory_to_delete(EXAMPLE, &disk), 48381165);
+    }
+
+    #[test]
+    fn deletes_directories_with_each_strategy() {
+        let filesystem = build_filesystem(&parse_commands(STRATEGY_EXAMPLE).unwrap());
+        // The disk is full, so 250 bytes must be freed
+        let disk = Disk {
+            capacity: 960,
+            required_free_space: 250,
+        };
+        let paths = |strategy| -> Vec<String> {
+            let mut paths: Vec<String> = filesystem
+                .directories_to_delete(&disk, strategy)
+                .unwrap()
+                .into_iter()
+                .map(|(path, _)| path)
+                .collect();
+            paths.sort();
+            paths
+        };
+
+        assert_eq!(paths(DeletionStrategy::SmallestDirectory), vec!["/a"]);
+        assert_eq!(paths(DeletionStrategy::FewestDirectories), vec!["/a"]);
+        assert_eq!(paths(DeletionStrategy::FewestBytes), vec!["/a/x", "/b", "/c"]);
+
+        let no_room = Disk {
+            capacity: 960,
+            required_free_space: 900,
+        };
+        // Only deleting everything frees up enough space, whatever the strategy
+        for strategy in [
+            DeletionStrategy::SmallestDirectory,
+            DeletionStrategy::FewestDirectories,
+            DeletionStrategy::FewestBytes,
+        ] {
+            assert_eq!(
+                filesystem.directories_to_delete(&no_room, strategy),
+                Some(vec![(String::from("/"), 960)])
+            );
+        }
+
+        let too_small = Disk {
+            capacity: 960,
+            required_free_space: 1000,
+        };
+        assert_eq!(
+            filesystem.directories_to_delete(&too_small, DeletionStrategy::FewestBytes),
+            None
+        );
+        assert_eq!(
+            filesystem.directories_to_delete(&PUZZLE_DISK, DeletionStrategy::FewestBytes),
+            Some(vec![])
+        );
+    }
+
+    #[test]
+    fn deletion_strategies_agree_on_the_puzzle() {
+        let filesystem = build_filesystem(&parse_commands(PUZZLE_INPUT).unwrap());
+        let freed = |strategy| -> u64 {
+            filesystem
+                .directories_to_delete(&PUZZLE_DISK, strategy)
+                .unwrap()
+                .iter()
+                .map(|(_, size)| size)
+                .sum()
+        };
+
+        // One directory is always enough, since the root can be deleted, so the fewest directories is the smallest one
+        assert_eq!(
+            freed(DeletionStrategy::FewestDirectories),
+            freed(DeletionStrategy::SmallestDirectory)
+        );
+        assert!(freed(DeletionStrategy::FewestBytes) <= freed(DeletionStrategy::SmallestDirectory));
+        assert!(freed(DeletionStrategy::FewestBytes) >= filesystem.space_to_free(&PUZZLE_DISK));
     }
+
+    const STRATEGY_EXAMPLE: &str = r#"$ cd /
+$ ls
+dir a
+dir b
+dir c
+dir big
+100 root.txt
+$ cd a
+$ ls
+dir x
+210 a.txt
+$ cd x
+$ ls
+50 x.txt
+$ cd /b
+$ ls
+100 b.txt
+$ cd /c
+$ ls
+100 c.txt
+$ cd /big
+$ ls
+400 big.txt
+"#;
 }
@@ -1,9 +1,10 @@
-use std::ops::Range;
+use std::{ops::Range, time::Instant};
 
-use twentytwo::{print_solution, read_from_stdin};
+use twentytwo::{print_solution, read_from_stdin, util};
 
 fn main() {
     let input = read_from_stdin();
+    let options = Options::from_args();
 
     print_solution(
         8,
@@ -18,16 +19,129 @@ fn main() {
         "Consider each tree on your map. What is the highest scenic score possible for any tree?",
         format!("{}", max_scenic_score(&input)),
     );
+
+    if options.visible {
+        let (trees, _, _) = parse_trees(&input);
+        println!("{}", render_visibility(&trees, &visibility_map(&input)));
+    }
+
+    if options.best {
+        let (x, y, score) = best_tree(&input);
+        println!(
+            "The best tree is at x={}, y={} with a scenic score of {}\n",
+            x, y, score
+        );
+    }
+
+    if let Some(path) = &options.heatmap {
+        std::fs::write(path, scenic_heatmap_png(&scenic_score_map(&input), 8)).expect("write heatmap to file");
+    }
+
+    if let Some(size) = options.benchmark {
+        benchmark(size);
+    }
+}
+
+/// Time the sweeping and the naive implementations on a generated `size` x `size` forest
+fn benchmark(size: usize) {
+    let (trees, _, _) = parse_trees(&generate_forest(size, 2022));
+
+    let timed = |name: &str, run: &dyn Fn() -> (usize, usize)| {
+        let start = Instant::now();
+        let (visible, best_score) = run();
+        println!(
+            "{:<6} {} visible trees, best scenic score {} in {:?}",
+            name,
+            visible,
+            best_score,
+            start.elapsed()
+        );
+    };
+
+    println!("Benchmarking a {}x{} forest", size, size);
+    timed("sweep", &|| {
+        summarize(&sweep_visibility(&trees), &sweep_scenic_scores(&trees))
+    });
+    timed("naive", &|| {
+        summarize(&naive_visibility(&trees), &naive_scenic_scores(&trees))
+    });
+    println!();
+}
+
+fn summarize(visibility: &[Vec<bool>], scores: &[Vec<usize>]) -> (usize, usize) {
+    (
+        visibility.iter().flatten().filter(|visible| **visible).count(),
+        scores.iter().flatten().copied().max().unwrap_or(0),
+    )
+}
+
+/// A forest of pseudo random heights, the same for the same seed
+fn generate_forest(size: usize, seed: u64) -> String {
+    // xorshift64, which never leaves zero, so the seed is nudged away from it
+    let mut state = seed | 1;
+
+    (0..size)
+        .map(|_| {
+            let row: String = (0..size)
+                .map(|_| {
+                    state ^= state << 13;
+                    state ^= state >> 7;
+                    state ^= state << 17;
+                    char::from(b'0' + (state % 10) as u8)
+                })
+                .collect();
+
+            row + "\n"
+        })
+        .collect()
+}
+
+struct Options {
+    /// Print the grid with the visible trees highlighted
+    visible: bool,
+    /// Print the coordinates of the tree with the highest scenic score
+    best: bool,
+    /// Write the scenic scores to this PNG file
+    heatmap: Option<String>,
+    /// Compare the implementations on a generated forest of this size
+    benchmark: Option<usize>,
+}
+
+impl Options {
+    fn from_args() -> Self {
+        let args = util::args();
+
+        Options {
+            visible: util::has_flag(&args, "--visible"),
+            best: util::has_flag(&args, "--best"),
+            heatmap: util::flag_value(&args, "--heatmap").map(String::from),
+            benchmark: util::parse_flag(&args, "--benchmark"),
+        }
+    }
 }
 
 // D8P1
 fn count_visible_trees(input: &str) -> usize {
-    let (trees, max_x, max_y) = parse_trees(input);
+    visibility_map(input)
+        .iter()
+        .flatten()
+        .filter(|visible| **visible)
+        .count()
+}
+
+/// Whether each tree is visible from outside the grid, indexed like the trees as `[y][x]`
+fn visibility_map(input: &str) -> Vec<Vec<bool>> {
+    let (trees, _, _) = parse_trees(input);
+    sweep_visibility(&trees)
+}
+
+/// Walk from every tree to each edge, which is O(n³) on an n x n forest
+fn naive_visibility(trees: &Trees) -> Vec<Vec<bool>> {
+    let (max_x, max_y) = (trees.first().map(|row| row.len()).unwrap_or(0), trees.len());
 
     (0..max_y)
-        .flat_map(|y| (0..max_x).map(move |x| (x, y)))
-        .filter(|(x, y)| is_tree_visible(&trees, *x, *y, max_x, max_y))
-        .count()
+        .map(|y| (0..max_x).map(|x| is_tree_visible(trees, x, y, max_x, max_y)).collect())
+        .collect()
 }
 
 fn is_tree_visible(trees: &Trees, x: usize, y: usize, max_x: usize, max_y: usize) -> bool {
@@ -42,13 +156,36 @@ fn is_tree_visible(trees: &Trees, x: usize, y: usize, max_x: usize, max_y: usize
 
 // D8P2
 fn max_scenic_score(input: &str) -> usize {
-    let (trees, max_x, max_y) = parse_trees(input);
+    let (_, _, score) = best_tree(input);
+    score
+}
+
+/// The scenic score of each tree, indexed like the trees as `[y][x]`
+fn scenic_score_map(input: &str) -> Vec<Vec<usize>> {
+    let (trees, _, _) = parse_trees(input);
+    sweep_scenic_scores(&trees)
+}
+
+/// Walk from every tree until the view is blocked, which is O(n³) on an n x n forest
+fn naive_scenic_scores(trees: &Trees) -> Vec<Vec<usize>> {
+    let (max_x, max_y) = (trees.first().map(|row| row.len()).unwrap_or(0), trees.len());
 
     (0..max_y)
-        .flat_map(|y| (0..max_x).map(move |x| (x, y)))
-        .map(|(x, y)| scenic_score(&trees, x, y, max_x, max_y))
-        .max()
-        .unwrap()
+        .map(|y| (0..max_x).map(|x| scenic_score(trees, x, y, max_x, max_y)).collect())
+        .collect()
+}
+
+/// The `(x, y, score)` of the tree with the highest scenic score. Ties go to the first tree in reading order.
+fn best_tree(input: &str) -> (usize, usize, usize) {
+    scenic_score_map(input)
+        .iter()
+        .enumerate()
+        .flat_map(|(y, row)| row.iter().enumerate().map(move |(x, score)| (x, y, *score)))
+        .fold(None, |best: Option<(usize, usize, usize)>, tree| match best {
+            Some(best) if best.2 >= tree.2 => Some(best),
+            _ => Some(tree),
+        })
+        .expect("at least one tree")
 }
 
 // This took me a while to get right 😅
@@ -76,6 +213,97 @@ fn scenic_score(trees: &Trees, x: usize, y: usize, max_x: usize, max_y: usize) -
         .product()
 }
 
+// Sweeps -->
+// Instead of walking out from every tree, every row and column is swept once in each direction,
+// carrying what has been seen so far. That makes both maps O(n²) on an n x n forest.
+
+/// Visible when taller than every tree before it in at least one direction
+fn sweep_visibility(trees: &Trees) -> Vec<Vec<bool>> {
+    let [west, east, north, south] = sweep_all_directions(trees, visible_from_start);
+
+    combine(&[west, east, north, south], |a: bool, b| a || b)
+}
+
+/// The product of the viewing distances in each direction
+fn sweep_scenic_scores(trees: &Trees) -> Vec<Vec<usize>> {
+    let [west, east, north, south] = sweep_all_directions(trees, viewing_distances);
+
+    combine(&[west, east, north, south], |a: usize, b| a * b)
+}
+
+/// Whether each tree in a line is taller than every tree before it
+fn visible_from_start(heights: &[u8]) -> Vec<bool> {
+    let mut tallest: Option<u8> = None;
+
+    heights
+        .iter()
+        .map(|height| {
+            let visible = tallest.is_none_or(|tallest| *height > tallest);
+            tallest = tallest.max(Some(*height));
+            visible
+        })
+        .collect()
+}
+
+/// How far each tree in a line can see back towards the start. The stack holds the trees that can still
+/// block a view, which are always decreasing in height, so every tree is pushed and popped at most once.
+fn viewing_distances(heights: &[u8]) -> Vec<usize> {
+    let mut blockers: Vec<usize> = Vec::new();
+
+    heights
+        .iter()
+        .enumerate()
+        .map(|(i, height)| {
+            while blockers.last().is_some_and(|blocker| heights[*blocker] < *height) {
+                blockers.pop();
+            }
+
+            let distance = blockers.last().map(|blocker| i - blocker).unwrap_or(i);
+            blockers.push(i);
+            distance
+        })
+        .collect()
+}
+
+/// Run `look_back` over every line of trees as seen when looking west, east, north and south,
+/// and put the results back on the grid as `[y][x]`
+fn sweep_all_directions<T: Copy>(trees: &Trees, look_back: impl Fn(&[u8]) -> Vec<T>) -> [Vec<Vec<T>>; 4] {
+    let (max_x, max_y) = (trees.first().map(|row| row.len()).unwrap_or(0), trees.len());
+    let columns: Vec<Row> = (0..max_x).map(|x| trees.iter().map(|row| row[x]).collect()).collect();
+    let reversed = |line: &[u8]| -> Vec<T> {
+        let mut result = look_back(&line.iter().rev().copied().collect::<Row>());
+        result.reverse();
+        result
+    };
+    let transposed = |by_column: Vec<Vec<T>>| -> Vec<Vec<T>> {
+        (0..max_y)
+            .map(|y| by_column.iter().map(|column| column[y]).collect())
+            .collect()
+    };
+
+    [
+        trees.iter().map(|row| look_back(row)).collect(),
+        trees.iter().map(|row| reversed(row)).collect(),
+        transposed(columns.iter().map(|column| look_back(column)).collect()),
+        transposed(columns.iter().map(|column| reversed(column)).collect()),
+    ]
+}
+
+fn combine<T: Copy>(maps: &[Vec<Vec<T>>; 4], combine_values: impl Fn(T, T) -> T) -> Vec<Vec<T>> {
+    let [first, rest @ ..] = maps;
+
+    first
+        .iter()
+        .enumerate()
+        .map(|(y, row)| {
+            row.iter()
+                .enumerate()
+                .map(|(x, value)| rest.iter().fold(*value, |acc, map| combine_values(acc, map[y][x])))
+                .collect()
+        })
+        .collect()
+}
+
 // Ranges and iterators -->
 fn west_range(x: usize) -> Range<usize> {
     0..x
@@ -113,6 +341,80 @@ fn south_iterator(x: usize, y: usize, max_y: usize) -> impl Iterator<Item = (usi
     south_range(y, max_y).map(move |y| (x, y))
 }
 
+// Rendering -->
+
+const VISIBLE_COLOR: &str = "\x1b[1;32m";
+const HIDDEN_COLOR: &str = "\x1b[2m";
+const RESET_COLOR: &str = "\x1b[0m";
+
+/// The grid of tree heights, with visible trees in bold green and hidden trees dimmed
+fn render_visibility(trees: &Trees, visibility: &[Vec<bool>]) -> String {
+    trees
+        .iter()
+        .zip(visibility)
+        .map(|(row, visible_row)| {
+            let line: String = row
+                .iter()
+                .zip(visible_row)
+                .map(|(height, visible)| {
+                    let color = if *visible { VISIBLE_COLOR } else { HIDDEN_COLOR };
+                    format!("{}{}{}", color, height, RESET_COLOR)
+                })
+                .collect();
+
+            line + "\n"
+        })
+        .collect()
+}
+
+/// An RGB PNG heatmap of the scenic scores, each tree scaled up to a square of `scale` pixels.
+/// Scores span several orders of magnitude, so the colors follow the logarithm of the score,
+/// going from black through red and yellow to white for the best tree.
+fn scenic_heatmap_png(scores: &[Vec<usize>], scale: usize) -> Vec<u8> {
+    let scale = scale.max(1);
+    let height = scores.len();
+    let width = scores.first().map(|row| row.len()).unwrap_or(0);
+    let max_score = scores.iter().flatten().copied().max().unwrap_or(0);
+    let mut png = Vec::new();
+
+    let mut encoder = png::Encoder::new(&mut png, (width * scale) as u32, (height * scale) as u32);
+    encoder.set_color(png::ColorType::Rgb);
+    encoder.set_depth(png::BitDepth::Eight);
+
+    let data: Vec<u8> = scores
+        .iter()
+        .flat_map(|row| {
+            let line: Vec<u8> = row
+                .iter()
+                .flat_map(|score| {
+                    let intensity = if max_score == 0 {
+                        0.0
+                    } else {
+                        (*score as f64).ln_1p() / (max_score as f64).ln_1p()
+                    };
+
+                    std::iter::repeat_n(heat_color(intensity), scale).flatten()
+                })
+                .collect();
+
+            std::iter::repeat_n(line, scale).flatten()
+        })
+        .collect();
+
+    let mut writer = encoder.write_header().expect("write png header");
+    writer.write_image_data(&data).expect("write png data");
+    writer.finish().expect("finish png");
+
+    png
+}
+
+/// Black at 0.0, red at 1/3, yellow at 2/3 and white at 1.0
+fn heat_color(intensity: f64) -> [u8; 3] {
+    let channel = |start: f64| ((intensity - start) * 3.0).clamp(0.0, 1.0) * 255.0;
+
+    [channel(0.0) as u8, channel(1.0 / 3.0) as u8, channel(2.0 / 3.0) as u8]
+}
+
 fn get_height(trees: &Trees, x: usize, y: usize) -> u8 {
     trees[y][x]
 }
@@ -142,7 +444,11 @@ fn parse_trees(input: &str) -> (Trees, usize, usize) {
 
 #[cfg(test)]
 mod tests {
-    use crate::{count_visible_trees, max_scenic_score};
+    use crate::{
+        best_tree, count_visible_trees, generate_forest, heat_color, max_scenic_score, naive_scenic_scores,
+        naive_visibility, parse_trees, render_visibility, scenic_heatmap_png, scenic_score_map, sweep_scenic_scores,
+        sweep_visibility, viewing_distances, visibility_map,
+    };
 
     const PUZZLE_INPUT: &str = include_str!("../../puzzle_inputs/day8.txt");
 
@@ -164,7 +470,68 @@ mod tests {
     }
 
     #[test]
-    fn solves_p1() {
-        assert_eq!(count_visible_trees(PUZZLE_INPUT), 1684);
+    fn maps_visibility_of_every_tree() {
+        let visibility = visibility_map(EXAMPLE);
+
+        assert_eq!(visibility[0], vec![true; 5]);
+        assert_eq!(visibility[1], vec![true, true, true, false, true]);
+        assert_eq!(visibility[2], vec![true, true, false, true, true]);
+        assert_eq!(visibility[3], vec![true, false, true, false, true]);
+        assert_eq!(visibility[4], vec![true; 5]);
+    }
+
+    #[test]
+    fn maps_scenic_score_of_every_tree() {
+        let scores = scenic_score_map(EXAMPLE);
+
+        assert_eq!(scores[1][2], 4);
+        assert_eq!(scores[3][2], 8);
+        assert_eq!(scores[0], vec![0; 5]);
+        assert_eq!(best_tree(EXAMPLE), (2, 3, 8));
+    }
+
+    #[test]
+    fn highlights_visible_trees() {
+        let (trees, _, _) = parse_trees(EXAMPLE);
+        let rendered = render_visibility(&trees, &visibility_map(EXAMPLE));
+        let second_row = rendered.lines().nth(1).unwrap();
+
+        assert_eq!(rendered.lines().count(), 5);
+        assert!(second_row.ends_with("\x1b[2m1\x1b[0m\x1b[1;32m2\x1b[0m"));
+    }
+
+    #[test]
+    fn writes_a_heatmap() {
+        let png = scenic_heatmap_png(&scenic_score_map(EXAMPLE), 3);
+        let decoder = png::Decoder::new(png.as_slice());
+        let reader = decoder.read_info().unwrap();
+
+        assert_eq!((reader.info().width, reader.info().height), (15, 15));
+        assert_eq!(heat_color(0.0), [0, 0, 0]);
+        assert_eq!(heat_color(1.0), [255, 255, 255]);
+    }
+
+    #[test]
+    fn sees_back_until_a_tree_at_least_as_tall() {
+        assert_eq!(viewing_distances(&[3, 0, 3, 7, 3]), vec![0, 1, 2, 3, 1]);
+        assert_eq!(viewing_distances(&[9, 1, 2, 3, 9]), vec![0, 1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn sweeps_agree_with_the_naive_walks() {
+        let forests = [
+            String::from(EXAMPLE),
+            String::from(PUZZLE_INPUT),
+            generate_forest(150, 1),
+            generate_forest(150, 2),
+            generate_forest(1, 3),
+        ];
+
+        for forest in forests.iter() {
+            let (trees, _, _) = parse_trees(forest);
+
+            assert_eq!(sweep_visibility(&trees), naive_visibility(&trees));
+            assert_eq!(sweep_scenic_scores(&trees), naive_scenic_scores(&trees));
+        }
     }
 }
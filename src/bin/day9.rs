@@ -1,56 +1,203 @@
-use std::{cmp::Ordering, collections::HashSet, hash::Hash};
+use std::{
+    borrow::Cow, cmp::Ordering, collections::HashSet, error::Error, fmt::Display, fs::File, hash::Hash, path::Path,
+    str::FromStr,
+};
 
-use twentytwo::{print_solution, read_from_stdin};
+use twentytwo::{print_solution, read_from_stdin, util};
 
 fn main() {
     let input = read_from_stdin();
+    let instructions = parse_input(&input).expect("parse instructions");
+    let options = Options::from_args();
+
+    // A knot only follows the knots in front of it, so the tail of a rope with two knots
+    // moves just like the second knot of a rope with ten knots. One simulation answers both parts.
+    let visited_positions = visited_positions_per_knot(&instructions, 10, options.follow_rule.as_ref());
 
     print_solution(
         9,
         1,
         "Simulate your complete hypothetical series of motions. How many positions does the tail of the rope visit at least once?",
-        format!("{}", solve_for_nibble_size_2(&input))
+        format!("{}", visited_positions[1].len())
     );
 
     print_solution(
         9,
         2,
         "Simulate your complete series of motions on a larger rope with ten knots. How many positions does the tail of the rope visit at least once?",
-        format!("{}", solve_for_nibble_size_10(&input))
+        format!("{}", visited_positions[9].len())
     );
+
+    if options.render || options.gif.is_some() || options.frames_dir.is_some() {
+        draw_rope(&instructions, &options);
+    }
+}
+
+struct Options {
+    /// How knots follow each other, like `chebyshev:1` (the puzzle's rule) or `manhattan:2`
+    follow_rule: Box<dyn FollowRule>,
+    /// Print the rope after every instruction
+    render: bool,
+    /// The number of knots in the drawn rope
+    knots: usize,
+    /// Write the rope to an animated GIF
+    gif: Option<String>,
+    /// Write every drawing to a file in this directory
+    frames_dir: Option<String>,
+}
+
+impl Options {
+    fn from_args() -> Self {
+        let args = util::args();
+
+        let follow_rule: Box<dyn FollowRule> = match util::flag_value(&args, "--follow") {
+            None => Box::new(PUZZLE_RULE),
+            Some(rule) => match rule.split_once(':').map(|(kind, slack)| (kind, slack.parse::<u32>())) {
+                Some(("chebyshev", Ok(slack))) => Box::new(ChebyshevSlack(slack)),
+                Some(("manhattan", Ok(slack))) => Box::new(ManhattanSlack(slack)),
+                _ => panic!(
+                    "unknown follow rule '{}', expected chebyshev:<k> or manhattan:<k>",
+                    rule
+                ),
+            },
+        };
+
+        Options {
+            follow_rule,
+            render: util::has_flag(&args, "--render"),
+            knots: util::parse_flag(&args, "--knots").unwrap_or(10),
+            gif: util::flag_value(&args, "--gif").map(String::from),
+            frames_dir: util::flag_value(&args, "--frames").map(String::from),
+        }
+    }
 }
 
+fn draw_rope(instructions: &[Instruction], options: &Options) {
+    let frames = rope_after_each_instruction(instructions, options.knots, options.follow_rule.as_ref());
+    let visited_positions = visited_positions_per_knot(instructions, options.knots, options.follow_rule.as_ref());
+    let tail_visited = &visited_positions[options.knots - 1];
+    let bounds = Bounds::around(
+        tail_visited
+            .iter()
+            .chain(frames.iter().flat_map(|frame| frame.rope.knots())),
+    );
+
+    if options.render {
+        for frame in frames.iter() {
+            println!("{}", frame.render(&bounds));
+        }
+
+        println!("== Visited by the tail ==\n\n{}", render_visited(tail_visited, &bounds));
+    }
+
+    if let Some(dir) = &options.frames_dir {
+        std::fs::create_dir_all(dir).expect("create frames directory");
+
+        for (index, frame) in frames.iter().enumerate() {
+            let path = Path::new(dir).join(format!("frame_{:05}.txt", index));
+            std::fs::write(path, frame.render(&bounds)).expect("write frame");
+        }
+
+        let path = Path::new(dir).join("visited.txt");
+        std::fs::write(path, render_visited(tail_visited, &bounds)).expect("write visited positions");
+    }
+
+    if let Some(path) = &options.gif {
+        if let Err(error) = write_gif(path, &frames, &bounds, 4) {
+            println!("{}\n", error);
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 enum Direction {
     Left,
     Right,
     Up,
     Down,
+    UpLeft,
+    UpRight,
+    DownLeft,
+    DownRight,
 }
 
+impl Direction {
+    const ALL: [(&'static str, Direction); 8] = [
+        ("L", Direction::Left),
+        ("R", Direction::Right),
+        ("U", Direction::Up),
+        ("D", Direction::Down),
+        ("UL", Direction::UpLeft),
+        ("UR", Direction::UpRight),
+        ("DL", Direction::DownLeft),
+        ("DR", Direction::DownRight),
+    ];
+
+    /// The change in (x, y) of a single step, where up is positive y
+    fn delta(&self) -> (i32, i32) {
+        match self {
+            Direction::Left => (-1, 0),
+            Direction::Right => (1, 0),
+            Direction::Up => (0, 1),
+            Direction::Down => (0, -1),
+            Direction::UpLeft => (-1, 1),
+            Direction::UpRight => (1, 1),
+            Direction::DownLeft => (-1, -1),
+            Direction::DownRight => (1, -1),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
 struct Instruction {
     direction: Direction,
-    amount: u8,
+    amount: u32,
 }
 
-impl From<&str> for Instruction {
-    fn from(input: &str) -> Self {
-        let (str_direction, amount) = input.split_at(1);
+impl Display for Instruction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let (name, _) = Direction::ALL
+            .iter()
+            .find(|(_, direction)| *direction == self.direction)
+            .expect("every direction has a name");
 
-        let direction = match str_direction {
-            "R" => Direction::Right,
-            "L" => Direction::Left,
-            "U" => Direction::Up,
-            "D" => Direction::Down,
-            _ => panic!("Unknown direction"),
-        };
+        write!(f, "{} {}", name, self.amount)
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+struct ParseInstructionError(String);
+
+impl Error for ParseInstructionError {}
+
+impl Display for ParseInstructionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Failed to parse instruction: '{}'", self.0)
+    }
+}
+
+impl FromStr for Instruction {
+    type Err = ParseInstructionError;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let error = || ParseInstructionError(String::from(input));
+        let (str_direction, amount) = input.split_once(' ').ok_or_else(error)?;
+
+        let (_, direction) = Direction::ALL
+            .iter()
+            .find(|(name, _)| *name == str_direction)
+            .ok_or_else(error)?;
 
-        let amount = amount[1..].parse::<u8>().expect("parse amount");
+        let amount = amount.trim().parse::<u32>().map_err(|_| error())?;
 
-        Instruction { direction, amount }
+        Ok(Instruction {
+            direction: *direction,
+            amount,
+        })
     }
 }
 
-#[derive(Copy, Clone, Hash, PartialEq, Eq)]
+#[derive(Copy, Clone, Debug, Hash, PartialEq, Eq)]
 struct Position(i32, i32);
 
 impl Position {
@@ -59,12 +206,9 @@ impl Position {
     }
 
     fn step_in_direction(&mut self, direction: &Direction) {
-        match direction {
-            Direction::Left => self.0 -= 1,
-            Direction::Right => self.0 += 1,
-            Direction::Up => self.1 += 1,
-            Direction::Down => self.1 -= 1,
-        }
+        let (delta_x, delta_y) = direction.delta();
+        self.0 += delta_x;
+        self.1 += delta_y;
     }
 
     fn move_diagonally(&mut self, head_position: &Position) {
@@ -81,78 +225,386 @@ impl Position {
         };
     }
 
-    fn touches_head_position(&self, head_position: &Position) -> bool {
-        let delta_x = (self.0 - head_position.0).abs();
-        let delta_y = (self.1 - head_position.1).abs();
+    /// Step once towards the head, along the axis where it is furthest away
+    fn move_along_longest_axis(&mut self, head_position: &Position) {
+        if self.0.abs_diff(head_position.0) >= self.1.abs_diff(head_position.1) {
+            self.0 += (head_position.0 - self.0).signum();
+        } else {
+            self.1 += (head_position.1 - self.1).signum();
+        }
+    }
+
+    fn chebyshev_distance(&self, other: &Position) -> u32 {
+        self.0.abs_diff(other.0).max(self.1.abs_diff(other.1))
+    }
 
-        (delta_x == 0 || delta_x == 1) && (delta_y == 0 || delta_y == 1)
+    fn manhattan_distance(&self, other: &Position) -> u32 {
+        self.0.abs_diff(other.0) + self.1.abs_diff(other.1)
     }
 }
 
-// Weeeeee, const generics! 🎉
-struct Nibble<const C: usize>([Position; C]);
+/// How a knot follows the knot in front of it, after that knot has taken a step
+trait FollowRule {
+    /// The new position of the knot
+    fn follow(&self, knot: Position, leader: Position) -> Position;
+}
 
-impl<const C: usize> Nibble<C> {
-    fn new() -> Self {
-        Nibble([Position::new(); C])
+/// The knot stays put while it is within k steps of the leader in any direction, including diagonals,
+/// otherwise it steps once towards the leader, moving diagonally if needed
+struct ChebyshevSlack(u32);
+
+impl FollowRule for ChebyshevSlack {
+    fn follow(&self, mut knot: Position, leader: Position) -> Position {
+        if knot.chebyshev_distance(&leader) > self.0 {
+            knot.move_diagonally(&leader);
+        }
+
+        knot
     }
+}
+
+/// The knot stays put while it is within k horizontal and vertical steps of the leader,
+/// otherwise it steps towards the leader. A diagonal step closes two steps of distance, so the knot
+/// only moves diagonally when it is more than one step too far away.
+struct ManhattanSlack(u32);
+
+impl FollowRule for ManhattanSlack {
+    fn follow(&self, mut knot: Position, leader: Position) -> Position {
+        let distance = knot.manhattan_distance(&leader);
+
+        if distance == self.0 + 1 {
+            knot.move_along_longest_axis(&leader);
+        } else if distance > self.0 {
+            knot.move_diagonally(&leader);
+        }
 
-    fn tail_position(&self) -> Position {
-        self.0[C - 1]
+        knot
+    }
+}
+
+/// The puzzle's rule: the knots must always be touching, diagonals included
+const PUZZLE_RULE: ChebyshevSlack = ChebyshevSlack(1);
+
+// Const generics were fun, but now the number of knots is chosen at runtime
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Rope(Vec<Position>);
+
+impl Rope {
+    fn new(knot_count: usize) -> Self {
+        if knot_count == 0 {
+            panic!("A rope needs at least one knot");
+        }
+
+        Rope(vec![Position::new(); knot_count])
+    }
+
+    fn knots(&self) -> &[Position] {
+        &self.0
     }
 
     fn move_head(&mut self, direction: &Direction) {
         self.0[0].step_in_direction(direction)
     }
 
-    fn balance_tail(&mut self) {
-        (1..C).for_each(|i| {
-            let head_position = self.0[i - 1];
-
-            if !(self.0[i]).touches_head_position(&head_position) {
-                self.0[i].move_diagonally(&head_position);
-            }
+    fn balance_tail(&mut self, follow_rule: &dyn FollowRule) {
+        (1..self.0.len()).for_each(|i| {
+            self.0[i] = follow_rule.follow(self.0[i], self.0[i - 1]);
         })
     }
 }
 
-// D9P1
-fn solve_for_nibble_size_2(input: &str) -> usize {
-    let instructions = parse_input(input);
+/// The state of the rope after every single step of the head
+fn rope_states<'a>(
+    instructions: &'a [Instruction],
+    knot_count: usize,
+    follow_rule: &'a dyn FollowRule,
+) -> impl Iterator<Item = Rope> + 'a {
+    let mut rope = Rope::new(knot_count);
+
+    instructions
+        .iter()
+        .flat_map(|instruction| std::iter::repeat_n(&instruction.direction, instruction.amount as usize))
+        .map(move |direction| {
+            rope.move_head(direction);
+            rope.balance_tail(follow_rule);
+            rope.clone()
+        })
+}
+
+/// The positions visited by each knot of the rope, including where it started, indexed by knot
+fn visited_positions_per_knot(
+    instructions: &[Instruction],
+    knot_count: usize,
+    follow_rule: &dyn FollowRule,
+) -> Vec<HashSet<Position>> {
+    let start = Rope::new(knot_count);
+    let mut visited_positions: Vec<HashSet<Position>> =
+        start.knots().iter().map(|knot| HashSet::from([*knot])).collect();
+
+    for rope in rope_states(instructions, knot_count, follow_rule) {
+        for (visited, knot) in visited_positions.iter_mut().zip(rope.knots()) {
+            visited.insert(*knot);
+        }
+    }
 
-    let nibble: Nibble<2> = Nibble::new();
-    count_visited_positions(instructions, nibble)
+    visited_positions
 }
 
-// D9P2
-fn solve_for_nibble_size_10(input: &str) -> usize {
-    let instructions = parse_input(input);
+// Drawing -->
 
-    let nibble: Nibble<10> = Nibble::new();
-    count_visited_positions(instructions, nibble)
+/// The state of the rope at a point worth drawing
+struct Frame {
+    title: String,
+    rope: Rope,
 }
 
-fn count_visited_positions<const C: usize>(instructions: Vec<Instruction>, mut nibble: Nibble<C>) -> usize {
-    let mut visited_positions: HashSet<Position> = HashSet::new();
+impl Frame {
+    /// Draw the rope like the puzzle text, with a title above
+    fn render(&self, bounds: &Bounds) -> String {
+        format!("== {} ==\n\n{}", self.title, render_rope(&self.rope, bounds))
+    }
+}
+
+/// The initial state of the rope, followed by the state after each instruction
+fn rope_after_each_instruction(
+    instructions: &[Instruction],
+    knot_count: usize,
+    follow_rule: &dyn FollowRule,
+) -> Vec<Frame> {
+    let mut states = rope_states(instructions, knot_count, follow_rule);
+    let mut rope = Rope::new(knot_count);
+
+    let mut frames = vec![Frame {
+        title: String::from("Initial State"),
+        rope: rope.clone(),
+    }];
 
     for instruction in instructions {
-        for _ in 0..instruction.amount {
-            nibble.move_head(&instruction.direction);
-            nibble.balance_tail();
-            visited_positions.insert(nibble.tail_position());
+        if let Some(last_state) = states.by_ref().take(instruction.amount as usize).last() {
+            rope = last_state;
         }
+
+        frames.push(Frame {
+            title: format!("{}", instruction),
+            rope: rope.clone(),
+        });
     }
 
-    visited_positions.len()
+    frames
 }
 
-fn parse_input(input: &str) -> Vec<Instruction> {
-    input.lines().map(Instruction::from).collect::<Vec<Instruction>>()
+/// The smallest box around a set of positions, always including the start
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Bounds {
+    min_x: i32,
+    max_x: i32,
+    min_y: i32,
+    max_y: i32,
+}
+
+impl Bounds {
+    fn around<'a>(positions: impl Iterator<Item = &'a Position>) -> Self {
+        Bounds::spanning(Position::new(), positions)
+    }
+
+    /// The smallest box around `first` and the other positions
+    fn spanning<'a>(first: Position, positions: impl Iterator<Item = &'a Position>) -> Self {
+        positions.fold(
+            Bounds {
+                min_x: first.0,
+                max_x: first.0,
+                min_y: first.1,
+                max_y: first.1,
+            },
+            |bounds, position| Bounds {
+                min_x: bounds.min_x.min(position.0),
+                max_x: bounds.max_x.max(position.0),
+                min_y: bounds.min_y.min(position.1),
+                max_y: bounds.max_y.max(position.1),
+            },
+        )
+    }
+
+    fn width(&self) -> usize {
+        (self.max_x - self.min_x + 1) as usize
+    }
+
+    fn height(&self) -> usize {
+        (self.max_y - self.min_y + 1) as usize
+    }
+
+    /// Every position in the box, row by row from the top, since up is positive y
+    fn rows(&self) -> impl Iterator<Item = impl Iterator<Item = Position>> + '_ {
+        (self.min_y..=self.max_y)
+            .rev()
+            .map(|y| (self.min_x..=self.max_x).map(move |x| Position(x, y)))
+    }
+}
+
+/// The puzzle's names for the knots: H for the head, then T for the tail of a two knot rope, or numbers from 1
+fn knot_label(index: usize, knot_count: usize) -> char {
+    match index {
+        0 => 'H',
+        1 if knot_count == 2 => 'T',
+        index => char::from_digit(index as u32, 36).unwrap_or('*'),
+    }
+}
+
+/// Draw the rope in the puzzle's notation. A knot in front covers the knots behind it, and `s` marks the start.
+fn render_rope(rope: &Rope, bounds: &Bounds) -> String {
+    bounds
+        .rows()
+        .map(|row| {
+            row.map(
+                |position| match rope.knots().iter().position(|knot| *knot == position) {
+                    Some(index) => knot_label(index, rope.knots().len()),
+                    None if position == Position::new() => 's',
+                    None => '.',
+                },
+            )
+            .chain(std::iter::once('\n'))
+            .collect::<String>()
+        })
+        .collect()
+}
+
+/// Draw the visited positions as `#`, with `s` for the start
+fn render_visited(visited_positions: &HashSet<Position>, bounds: &Bounds) -> String {
+    bounds
+        .rows()
+        .map(|row| {
+            row.map(|position| {
+                if position == Position::new() {
+                    's'
+                } else if visited_positions.contains(&position) {
+                    '#'
+                } else {
+                    '.'
+                }
+            })
+            .chain(std::iter::once('\n'))
+            .collect::<String>()
+        })
+        .collect()
+}
+
+#[derive(Debug, PartialEq, Eq)]
+struct GifError(String);
+
+impl Error for GifError {}
+
+impl Display for GifError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Failed to write the GIF: {}", self.0)
+    }
+}
+
+/// Write the frames as an animated GIF, where every position is a square of `scale` pixels, or fewer if the
+/// image would be too large for a GIF. Only the first frame draws everything, the other frames redraw just
+/// the area around where the knots were and where they are now.
+fn write_gif(path: &str, frames: &[Frame], bounds: &Bounds, scale: usize) -> Result<(), GifError> {
+    const BACKGROUND: u8 = 0;
+    const START: u8 = 1;
+    const HEAD: u8 = 2;
+    const KNOT: u8 = 3;
+    const TAIL: u8 = 4;
+    let palette = [15, 15, 35, 90, 90, 110, 220, 50, 50, 240, 240, 240, 80, 200, 80];
+
+    let too_large = || {
+        GifError(format!(
+            "the rope moves over {}x{} positions, which is more than fits in a GIF",
+            bounds.width(),
+            bounds.height()
+        ))
+    };
+
+    let scale = scale.min(u16::MAX as usize / bounds.width().max(bounds.height()));
+    if scale == 0 {
+        return Err(too_large());
+    }
+
+    let pixels = |cells: usize| u16::try_from(cells * scale).map_err(|_| too_large());
+
+    let width = pixels(bounds.width())?;
+    let height = pixels(bounds.height())?;
+
+    let file = File::create(path).map_err(|error| GifError(error.to_string()))?;
+    let mut encoder = gif::Encoder::new(file, width, height, &palette).map_err(|error| GifError(error.to_string()))?;
+    encoder
+        .set_repeat(gif::Repeat::Infinite)
+        .map_err(|error| GifError(error.to_string()))?;
+
+    let mut previous_knots: Option<&[Position]> = None;
+
+    for frame in frames {
+        let knots = frame.rope.knots();
+
+        let area = match previous_knots {
+            None => *bounds,
+            Some(previous_knots) => Bounds::spanning(knots[0], previous_knots.iter().chain(knots.iter())),
+        };
+
+        let cells: Vec<u8> = area
+            .rows()
+            .flatten()
+            .map(|position| match knots.iter().position(|knot| *knot == position) {
+                Some(0) => HEAD,
+                Some(index) if index == knots.len() - 1 => TAIL,
+                Some(_) => KNOT,
+                None if position == Position::new() => START,
+                None => BACKGROUND,
+            })
+            .collect();
+
+        let buffer: Vec<u8> = cells
+            .chunks(area.width())
+            .flat_map(|row| {
+                let line: Vec<u8> = row.iter().flat_map(|cell| std::iter::repeat_n(*cell, scale)).collect();
+                std::iter::repeat_n(line, scale).flatten()
+            })
+            .collect();
+
+        let gif_frame = gif::Frame {
+            left: pixels((area.min_x - bounds.min_x) as usize)?,
+            top: pixels((bounds.max_y - area.max_y) as usize)?,
+            width: pixels(area.width())?,
+            height: pixels(area.height())?,
+            delay: 10,
+            // Keep the earlier frames below, since this frame only covers part of the image
+            dispose: gif::DisposalMethod::Keep,
+            buffer: Cow::Owned(buffer),
+            ..gif::Frame::default()
+        };
+
+        encoder
+            .write_frame(&gif_frame)
+            .map_err(|error| GifError(error.to_string()))?;
+        previous_knots = Some(knots);
+    }
+
+    Ok(())
+}
+
+/// Blank lines and comment lines starting with # are skipped
+fn parse_input(input: &str) -> Result<Vec<Instruction>, ParseInstructionError> {
+    input
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(Instruction::from_str)
+        .collect()
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::{solve_for_nibble_size_10, solve_for_nibble_size_2};
+    use crate::{
+        parse_input, render_visited, rope_after_each_instruction, rope_states, visited_positions_per_knot, write_gif,
+        Bounds, ChebyshevSlack, Direction, Instruction, ManhattanSlack, Position, Rope, PUZZLE_RULE,
+    };
+
+    fn count_tail_positions(input: &str, knot_count: usize) -> usize {
+        visited_positions_per_knot(&parse_input(input).unwrap(), knot_count, &PUZZLE_RULE)[knot_count - 1].len()
+    }
 
     const PUZZLE_INPUT: &str = include_str!("../../puzzle_inputs/day9.txt");
 
@@ -178,21 +630,222 @@ U 20
 
     #[test]
     fn solves_p1_example() {
-        assert_eq!(solve_for_nibble_size_2(EXAMPLE_1), 13);
+        assert_eq!(count_tail_positions(EXAMPLE_1, 2), 13);
     }
 
     #[test]
-    fn solves_p1() {
-        assert_eq!(solve_for_nibble_size_2(PUZZLE_INPUT), 5878);
+    fn solves_p2_example() {
+        assert_eq!(count_tail_positions(EXAMPLE_2, 10), 36);
     }
 
     #[test]
-    fn solves_p2_example() {
-        assert_eq!(solve_for_nibble_size_10(EXAMPLE_2), 36);
+    fn one_simulation_answers_every_knot() {
+        let visited_positions = visited_positions_per_knot(&parse_input(PUZZLE_INPUT).unwrap(), 10, &PUZZLE_RULE);
+
+        (1..10).for_each(|knot| {
+            assert_eq!(
+                visited_positions[knot - 1].len(),
+                visited_positions_per_knot(&parse_input(PUZZLE_INPUT).unwrap(), knot, &PUZZLE_RULE)[knot - 1].len()
+            );
+        });
+    }
+
+    #[test]
+    fn yields_the_rope_after_every_step() {
+        let instructions = parse_input(EXAMPLE_1).unwrap();
+        let states: Vec<Rope> = rope_states(&instructions, 10, &PUZZLE_RULE).collect();
+
+        assert_eq!(states.len(), 24);
+        assert_eq!(
+            states[23].knots(),
+            &[
+                Position(2, 2),
+                Position(1, 2),
+                Position(2, 2),
+                Position(3, 2),
+                Position(2, 2),
+                Position(1, 1),
+                Position(0, 0),
+                Position(0, 0),
+                Position(0, 0),
+                Position(0, 0),
+            ]
+        );
+    }
+
+    #[test]
+    fn renders_the_rope_like_the_puzzle() {
+        let frames = rope_after_each_instruction(&parse_input(EXAMPLE_1).unwrap(), 10, &PUZZLE_RULE);
+        let bounds = Bounds::around(frames.iter().flat_map(|frame| frame.rope.knots()));
+
+        assert_eq!(frames.len(), 9);
+        assert_eq!(
+            frames[8].render(&bounds),
+            r#"== R 2 ==
+
+......
+......
+.1H3..
+.5....
+6.....
+"#
+        );
+
+        let frames = rope_after_each_instruction(&parse_input(EXAMPLE_1).unwrap(), 2, &PUZZLE_RULE);
+
+        assert_eq!(
+            frames[0].render(&bounds),
+            r#"== Initial State ==
+
+......
+......
+......
+......
+H.....
+"#
+        );
+        assert_eq!(
+            frames[8].render(&bounds),
+            r#"== R 2 ==
+
+......
+......
+.TH...
+......
+s.....
+"#
+        );
+    }
+
+    #[test]
+    fn renders_the_positions_visited_by_the_tail() {
+        let visited_positions = visited_positions_per_knot(&parse_input(EXAMPLE_1).unwrap(), 2, &PUZZLE_RULE);
+        let bounds = Bounds::around(visited_positions.iter().flatten());
+
+        assert_eq!(
+            render_visited(&visited_positions[1], &bounds),
+            r#"..##..
+...##.
+.####.
+....#.
+s###..
+"#
+        );
+    }
+
+    #[test]
+    fn parses_diagonals_large_amounts_and_comments() {
+        let input = "# A comment\nUR 300\n\n  DL 2  \nR 1\n";
+
+        assert_eq!(
+            parse_input(input),
+            Ok(vec![
+                Instruction {
+                    direction: Direction::UpRight,
+                    amount: 300
+                },
+                Instruction {
+                    direction: Direction::DownLeft,
+                    amount: 2
+                },
+                Instruction {
+                    direction: Direction::Right,
+                    amount: 1
+                },
+            ])
+        );
+
+        assert_eq!(
+            parse_input("X 3").unwrap_err().to_string(),
+            "Failed to parse instruction: 'X 3'"
+        );
+        assert!(parse_input("R -3").is_err());
+    }
+
+    #[test]
+    fn moves_diagonally() {
+        let instructions = parse_input("UR 3\nDR 2").unwrap();
+        let rope = rope_states(&instructions, 3, &PUZZLE_RULE).last().unwrap();
+
+        assert_eq!(rope.knots(), &[Position(5, 1), Position(4, 1), Position(3, 1)]);
+    }
+
+    #[test]
+    fn follows_with_slack() {
+        let instructions = parse_input("R 5").unwrap();
+
+        let rope = rope_states(&instructions, 2, &ChebyshevSlack(3)).last().unwrap();
+        assert_eq!(rope.knots(), &[Position(5, 0), Position(2, 0)]);
+
+        let instructions = parse_input("R 3\nU 3").unwrap();
+
+        let rope = rope_states(&instructions, 2, &ChebyshevSlack(3)).last().unwrap();
+        assert_eq!(rope.knots(), &[Position(3, 3), Position(0, 0)]);
+
+        let rope = rope_states(&instructions, 2, &ManhattanSlack(3)).last().unwrap();
+        assert_eq!(rope.knots(), &[Position(3, 3), Position(2, 1)]);
+    }
+
+    #[test]
+    fn never_moves_a_knot_onto_its_leader() {
+        let instructions = parse_input("R 1\nU 1").unwrap();
+        let rope = rope_states(&instructions, 2, &ManhattanSlack(1)).last().unwrap();
+        assert_eq!(rope.knots(), &[Position(1, 1), Position(1, 0)]);
+
+        // The knots all start in the same place, so only check the knots that moved
+        let instructions = parse_input(EXAMPLE_2).unwrap();
+        let mut previous = Rope::new(10);
+        for rope in rope_states(&instructions, 10, &ManhattanSlack(1)) {
+            for knot in 1..10 {
+                if rope.knots()[knot] != previous.knots()[knot] {
+                    assert_eq!(rope.knots()[knot].manhattan_distance(&rope.knots()[knot - 1]), 1);
+                }
+            }
+            previous = rope;
+        }
+    }
+
+    #[test]
+    fn writes_only_the_changed_area_after_the_first_frame() {
+        let frames = rope_after_each_instruction(&parse_input(EXAMPLE_1).unwrap(), 10, &PUZZLE_RULE);
+        let bounds = Bounds::around(frames.iter().flat_map(|frame| frame.rope.knots()));
+        let path = std::env::temp_dir().join(format!("day9_{}.gif", std::process::id()));
+
+        write_gif(path.to_str().unwrap(), &frames, &bounds, 4).unwrap();
+
+        let mut decoder = gif::DecodeOptions::new()
+            .read_info(std::fs::File::open(&path).unwrap())
+            .unwrap();
+        assert_eq!((decoder.width(), decoder.height()), (24, 20));
+
+        let mut areas = Vec::new();
+        while let Some(frame) = decoder.read_next_frame().unwrap() {
+            areas.push((frame.left, frame.top, frame.width, frame.height));
+        }
+        std::fs::remove_file(path).unwrap();
+
+        assert_eq!(areas.len(), frames.len());
+        assert_eq!(areas[0], (0, 0, 24, 20));
+        // R 4 moves the head from the start to (4, 0), and the rest of the rope along behind it
+        assert_eq!(areas[1], (0, 16, 20, 4));
     }
 
     #[test]
-    fn solves_p2() {
-        assert_eq!(solve_for_nibble_size_10(PUZZLE_INPUT), 2405);
+    fn shrinks_gifs_that_would_be_too_large() {
+        let path = std::env::temp_dir().join(format!("day9_large_{}.gif", std::process::id()));
+        let write = |amount| {
+            let frames = rope_after_each_instruction(&parse_input(&format!("R {}", amount)).unwrap(), 2, &PUZZLE_RULE);
+            let bounds = Bounds::around(frames.iter().flat_map(|frame| frame.rope.knots()));
+            write_gif(path.to_str().unwrap(), &frames, &bounds, 4)
+        };
+
+        assert_eq!(write(17000), Ok(()));
+        let decoder = gif::DecodeOptions::new()
+            .read_info(std::fs::File::open(&path).unwrap())
+            .unwrap();
+        assert_eq!((decoder.width(), decoder.height()), (51003, 3));
+        std::fs::remove_file(&path).unwrap();
+
+        assert!(write(70000).is_err());
     }
 }
@@ -1,19 +1,4 @@
-use std::{
-    error::Error,
-    fmt::Display,
-    io::{stdin, BufReader, Read},
-};
-
-#[derive(Debug)]
-pub struct StdinReadError;
-
-impl Error for StdinReadError {}
-
-impl Display for StdinReadError {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "Failed to read from stdin.")
-    }
-}
+use std::io::{stdin, BufReader, Read};
 
 pub fn read_from_stdin() -> String {
     let stdin = stdin();
@@ -1,10 +1,84 @@
 use regex::Captures;
+use std::{fmt::Display, str::FromStr};
 
-pub fn regex_capture_to_u8(captures: &Captures, index: usize) -> u8 {
+/// Parse a capture group, failing instead of panicking when it doesn't fit the type
+pub fn parse_regex_capture<T: FromStr>(captures: &Captures, index: usize) -> Result<T, T::Err> {
     captures
         .get(index)
-        .unwrap()
-        .as_str()
-        .parse::<u8>()
-        .expect("parse capture to u8")
+        .map(|capture| capture.as_str())
+        .unwrap_or_default()
+        .parse::<T>()
+}
+
+/// The command line arguments, without the program name
+pub fn args() -> Vec<String> {
+    std::env::args().skip(1).collect()
+}
+
+/// Whether a flag like `--stats` was given
+pub fn has_flag(args: &[String], flag: &str) -> bool {
+    args.iter().any(|arg| arg == flag)
+}
+
+/// Every value given to a flag, in order, for flags that can be repeated like `--breakpoint 3 --breakpoint 7`.
+/// Panics if the flag isn't followed by a value, or is followed by another flag.
+pub fn flag_values<'a>(args: &'a [String], flag: &'a str) -> impl Iterator<Item = &'a str> {
+    args.iter()
+        .enumerate()
+        .filter(move |(_, arg)| *arg == flag)
+        .map(move |(index, _)| {
+            args.get(index + 1)
+                .filter(|value| !value.starts_with("--"))
+                .unwrap_or_else(|| panic!("{} needs a value", flag))
+                .as_str()
+        })
+}
+
+/// The value given to a flag like `--width 20`. When the flag is given more than once, the last value wins.
+pub fn flag_value<'a>(args: &'a [String], flag: &'a str) -> Option<&'a str> {
+    flag_values(args, flag).last()
+}
+
+/// The value given to a flag, parsed. Panics if it can't be parsed.
+pub fn parse_flag<T: FromStr>(args: &[String], flag: &str) -> Option<T> {
+    flag_value(args, flag).map(|value| {
+        value
+            .parse()
+            .unwrap_or_else(|_| panic!("invalid value '{}' for {}", value, flag))
+    })
+}
+
+/// The answer to print, or why there is none
+pub fn describe<T: Display, E: Display>(result: Result<T, E>) -> String {
+    match result {
+        Ok(answer) => answer.to_string(),
+        Err(error) => error.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{flag_value, flag_values, has_flag, parse_flag};
+
+    fn args(line: &str) -> Vec<String> {
+        line.split_whitespace().map(String::from).collect()
+    }
+
+    #[test]
+    fn reads_flags_and_values() {
+        let args = args("--stats --top 3 --breakpoint 1 --breakpoint 7 --top 5");
+
+        assert!(has_flag(&args, "--stats"));
+        assert!(!has_flag(&args, "--steps"));
+        assert_eq!(flag_value(&args, "--top"), Some("5"));
+        assert_eq!(parse_flag::<usize>(&args, "--top"), Some(5));
+        assert_eq!(flag_value(&args, "--crane"), None);
+        assert_eq!(flag_values(&args, "--breakpoint").collect::<Vec<_>>(), vec!["1", "7"]);
+    }
+
+    #[test]
+    #[should_panic(expected = "--top needs a value")]
+    fn does_not_take_another_flag_as_a_value() {
+        flag_value(&args("--top --stats"), "--top");
+    }
 }
@@ -0,0 +1,152 @@
+//! Runs every day's binary on its puzzle input and checks the printed answers against puzzle_inputs/answers.toml
+
+use std::{
+    collections::BTreeMap,
+    io::Write,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+    thread,
+};
+
+const ANSWERS: &str = include_str!("../puzzle_inputs/answers.toml");
+
+#[derive(Debug)]
+struct Answer {
+    day: u8,
+    part: u8,
+    input: String,
+    expected: String,
+}
+
+#[test]
+fn solves_every_puzzle() {
+    let answers = parse_answers(ANSWERS);
+
+    // Each binary solves both parts in one run, so run each day and input once, and all of them at the same time
+    let mut runs: BTreeMap<(u8, String), Vec<&Answer>> = BTreeMap::new();
+    for answer in answers.iter() {
+        runs.entry((answer.day, answer.input.clone())).or_default().push(answer);
+    }
+
+    let failures: Vec<String> = thread::scope(|scope| {
+        let handles: Vec<_> = runs
+            .iter()
+            .map(|((day, input), answers)| scope.spawn(move || check_run(*day, input, answers)))
+            .collect();
+
+        handles
+            .into_iter()
+            .flat_map(|handle| handle.join().expect("join run"))
+            .collect()
+    });
+
+    assert!(failures.is_empty(), "\n{}", failures.join("\n"));
+}
+
+/// Run one day on one input, and describe every answer that doesn't match
+fn check_run(day: u8, input: &str, answers: &[&Answer]) -> Vec<String> {
+    let output = run_day(day, input);
+
+    answers
+        .iter()
+        .filter_map(|answer| {
+            let actual = answer_from_output(&output, answer.day, answer.part);
+
+            match actual {
+                Some(actual) if actual == answer.expected => None,
+                _ => Some(format!(
+                    "Day {} part {} on {}: expected {}, got {:?}",
+                    answer.day, answer.part, answer.input, answer.expected, actual
+                )),
+            }
+        })
+        .collect()
+}
+
+fn run_day(day: u8, input: &str) -> String {
+    let input = std::fs::read(Path::new(env!("CARGO_MANIFEST_DIR")).join("puzzle_inputs").join(input))
+        .unwrap_or_else(|_| panic!("read {}", input));
+
+    let mut child = Command::new(binary_path(day))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap_or_else(|_| panic!("run day {}", day));
+
+    child
+        .stdin
+        .take()
+        .expect("stdin")
+        .write_all(&input)
+        .expect("write input");
+
+    let output = child.wait_with_output().expect("wait for output");
+    assert!(output.status.success(), "day {} exited with {}", day, output.status);
+
+    String::from_utf8(output.stdout).expect("utf-8 output")
+}
+
+/// Cargo only tells integration tests where each binary is by name, and they are all built next to each other
+fn binary_path(day: u8) -> PathBuf {
+    Path::new(env!("CARGO_BIN_EXE_day1")).with_file_name(format!("day{}{}", day, std::env::consts::EXE_SUFFIX))
+}
+
+/// The answer is the last line of the solution, which follows the header and the question printed by
+/// `print_solution` and ends at an empty line. Day 10 prints an image before its answer, for example.
+fn answer_from_output(output: &str, day: u8, part: u8) -> Option<String> {
+    let header = format!("Day {}, Part {} ", day, part);
+
+    output
+        .lines()
+        .skip_while(|line| !line.contains(&header))
+        .skip(2)
+        .take_while(|line| !line.is_empty())
+        .last()
+        .map(|line| line.to_string())
+}
+
+fn parse_answers(manifest: &str) -> Vec<Answer> {
+    let table: toml::Table = manifest.parse().expect("parse answers.toml");
+
+    table["answer"]
+        .as_array()
+        .expect("an array of answers")
+        .iter()
+        .map(|answer| {
+            let number = |key: &str| {
+                answer[key]
+                    .as_integer()
+                    .unwrap_or_else(|| panic!("{} is a number", key)) as u8
+            };
+            let text = |key: &str| {
+                answer[key]
+                    .as_str()
+                    .unwrap_or_else(|| panic!("{} is a string", key))
+                    .to_string()
+            };
+
+            Answer {
+                day: number("day"),
+                part: number("part"),
+                input: text("input"),
+                expected: text("expected"),
+            }
+        })
+        .collect()
+}
+
+#[test]
+fn has_both_answers_for_every_day() {
+    let answers = parse_answers(ANSWERS);
+
+    for day in 1..=15 {
+        for part in 1..=2 {
+            assert!(
+                answers.iter().any(|answer| answer.day == day && answer.part == part),
+                "no answer for day {} part {}",
+                day,
+                part
+            );
+        }
+    }
+}